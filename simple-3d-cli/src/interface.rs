@@ -22,6 +22,8 @@ impl simple_3d_core::Interface for CliInterface {
     ) -> Self {
         let stdout = stdout();
         let mut stdout = stdout.into_raw_mode().unwrap();
+        // A key at a time, as soon as it is pressed.
+        #[allow(clippy::unbuffered_bytes)]
         let mut stdin = async_stdin().bytes();
 
         let frame_duration = 10;
@@ -45,6 +47,7 @@ impl simple_3d_core::Interface for CliInterface {
                     Some(Ok(b'k')) => Some(Key::ArrowUp),
                     Some(Ok(b'h')) => Some(Key::ArrowLeft),
                     Some(Ok(b'l')) => Some(Key::ArrowRight),
+                    Some(Ok(byte)) if byte.is_ascii_graphic() => Some(Key::Char(byte as char)),
                     _ => None,
                 };
                 if let Some(pressed) = pressed {
@@ -71,6 +74,7 @@ impl simple_3d_core::Interface for CliInterface {
         (width as u32, height as u32)
     }
     fn wait(self) {
+        #[allow(clippy::unbuffered_bytes)]
         let mut stdin = async_stdin().bytes();
         loop {
             if let Some(Ok(b'q')) = stdin.next() {
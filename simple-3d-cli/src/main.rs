@@ -1,44 +1,44 @@
 use std::{
-    collections::HashMap,
-    io::{Read, stdout},
+    io::stdout,
+    pin::pin,
+    task::{Context, Poll, Waker},
 };
 
-use simple_3d_core::{
-    init, load_image, load_obj,
-    types::{mesh::Mesh, textures::Image},
-};
+use simple_3d_core::{LoadError, load_scene_assets};
 use termion::raw::IntoRawMode;
 
 mod interface;
 
 fn main() {
-    let (objects, images) = load_objects(&["cube.obj"]);
+    // Assets are looked up in the `assets` directory, the scene one included.
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "scene.toml".into());
+    let mut loading = pin!(load_scene_assets(&path, |path| std::future::ready(
+        load_binary_asset(path)
+    )));
+    // Files are read as they are asked for, so the loading never has to wait.
+    let Poll::Ready(loaded) = loading
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    else {
+        unreachable!("reading files does not wait");
+    };
+    let assets = match loaded {
+        Ok(assets) => assets,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
 
     stdout().into_raw_mode().unwrap();
-    let app = init::<interface::CliInterface>(objects, images);
+    let app = assets.init::<interface::CliInterface>();
     app.wait();
 }
 
-fn load_objects(paths: &[&str]) -> (Box<[Mesh]>, HashMap<Box<str>, Image>) {
-    let mut objects = Vec::new();
-    let mut images = HashMap::new();
-    for path in paths {
-        let object = load_obj(&load_binary_asset(path));
-        let texture = object.texture.clone();
-        let image = load_image(&load_binary_asset(&texture));
-        images.insert(texture, image);
-        objects.push(object);
-    }
-    (objects.into(), images)
-}
-
-fn load_binary_asset(path: &str) -> Vec<u8> {
-    let path = format!("../assets/{}", path);
-    let file = std::fs::File::open(path).expect("Unable to open file");
-    let mut reader = std::io::BufReader::new(file);
-    let mut buffer = Vec::new();
-    reader
-        .read_to_end(&mut buffer)
-        .expect("Unable to read file");
-    buffer
+fn load_binary_asset(path: &str) -> Result<Box<[u8]>, LoadError> {
+    std::fs::read(format!("../assets/{}", path))
+        .map(Vec::into_boxed_slice)
+        .map_err(|error| LoadError::Fetch(error.to_string().into()))
 }
@@ -0,0 +1,159 @@
+//! Clipping of triangles against the view volume in homogeneous clip space, before the perspective divide.
+
+use crate::types::vector::Vector;
+
+/// Planes of the view volume triangles are clipped against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clipping {
+    /// Only the near and far planes, which the perspective divide can not handle.
+    /// Parts outside the sides are left for the rasterizer to skip.
+    NearFar,
+    #[default]
+    All,
+}
+
+/// Signed distance to each plane of the view volume, negative outside: near, far, left, right, bottom, top.
+const PLANES: [fn(&Vector<4>) -> f64; 6] = [
+    |p| p[2] + p[3],
+    |p| p[3] - p[2],
+    |p| p[0] + p[3],
+    |p| p[3] - p[0],
+    |p| p[1] + p[3],
+    |p| p[3] - p[1],
+];
+
+impl Clipping {
+    fn planes(self) -> &'static [fn(&Vector<4>) -> f64] {
+        match self {
+            Clipping::NearFar => &PLANES[..2],
+            Clipping::All => &PLANES,
+        }
+    }
+}
+
+/// Corner of a triangle in clip space, with everything interpolated across the face.
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+    pub position: Vector<4>,
+    /// Position in world space, for lighting.
+    pub world: Vector<3>,
+    pub uv: Vector<2>,
+    pub normal: Vector<3>,
+    pub color: Vector<4>,
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self {
+            position: self.position + (other.position - self.position) * t,
+            world: self.world + (other.world - self.world) * t,
+            uv: self.uv + (other.uv - self.uv) * t,
+            normal: self.normal + (other.normal - self.normal) * t,
+            color: self.color + (other.color - self.color) * t,
+        }
+    }
+}
+
+/// Clips the triangle to the view volume, returning the convex polygon left, empty when nothing is.
+///
+/// Clip space is still linear, so the attributes of new corners are interpolated along the cut edges.
+pub fn clip_triangle(triangle: [ClipVertex; 3], clipping: Clipping) -> Vec<ClipVertex> {
+    let mut polygon = triangle.to_vec();
+    for plane in clipping.planes() {
+        let distance = |vertex: &ClipVertex| plane(&vertex.position);
+        if polygon.iter().all(|vertex| distance(vertex) >= 0.0) {
+            continue;
+        }
+
+        // Sutherland-Hodgman, keeping the inside corners and adding one where an edge crosses the plane.
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let (d_current, d_next) = (distance(current), distance(next));
+            if d_current >= 0.0 {
+                clipped.push(*current);
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                clipped.push(current.lerp(next, d_current / (d_current - d_next)));
+            }
+        }
+        if clipped.len() < 3 {
+            return Vec::new();
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f64; 4], uv: [f64; 2]) -> ClipVertex {
+        ClipVertex {
+            position: position.into(),
+            world: [position[0], position[1], position[2]].into(),
+            uv: uv.into(),
+            normal: [0.0, 0.0, 1.0].into(),
+            color: [1.0; 4].into(),
+        }
+    }
+
+    #[test]
+    fn test_clip_inside() {
+        let triangle = [
+            vertex([0.0, 0.0, 0.0, 1.0], [0.0, 0.0]),
+            vertex([0.5, 0.0, 0.0, 1.0], [1.0, 0.0]),
+            vertex([0.0, 0.5, 0.5, 1.0], [0.0, 1.0]),
+        ];
+        let clipped = clip_triangle(triangle, Clipping::All);
+        assert_eq!(clipped.len(), 3);
+        for (clipped, vertex) in clipped.iter().zip(triangle.iter()) {
+            assert_eq!(clipped.position, vertex.position);
+        }
+    }
+
+    #[test]
+    fn test_clip_near() {
+        // One corner behind the camera, where w is negative, cuts the triangle into a quad.
+        let triangle = [
+            vertex([0.0, 0.0, 0.0, 1.0], [0.0, 0.0]),
+            vertex([0.0, 0.0, -3.0, -1.0], [1.0, 0.0]),
+            vertex([0.5, 0.0, 0.0, 1.0], [0.0, 1.0]),
+        ];
+        let clipped = clip_triangle(triangle, Clipping::NearFar);
+        assert_eq!(clipped.len(), 4);
+        for vertex in clipped.iter() {
+            assert!(vertex.position[2] + vertex.position[3] >= -1e-12);
+        }
+        // A fifth of the way to the corner behind, the uvs are interpolated to match.
+        let new = clipped[1];
+        assert!((new.position - [0.0, 0.0, -0.6, 0.6].into()).magnitude() < 1e-12);
+        assert!((new.uv[0] - 0.2).abs() < 1e-12);
+
+        assert!(
+            clip_triangle(
+                triangle.map(|mut v| {
+                    v.position[3] = -1.0;
+                    v.position[2] = -3.0;
+                    v
+                }),
+                Clipping::All
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_clip_sides() {
+        let triangle = [
+            vertex([-2.0, 0.0, 0.0, 1.0], [0.0, 0.0]),
+            vertex([2.0, 0.0, 0.0, 1.0], [1.0, 0.0]),
+            vertex([0.0, 0.5, 0.0, 1.0], [0.5, 1.0]),
+        ];
+        assert_eq!(clip_triangle(triangle, Clipping::NearFar).len(), 3);
+        let clipped = clip_triangle(triangle, Clipping::All);
+        assert_eq!(clipped.len(), 5);
+        assert!(clipped.iter().all(|v| v.position[0].abs() <= 1.0));
+    }
+}
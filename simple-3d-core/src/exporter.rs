@@ -0,0 +1,187 @@
+use std::fmt::Write;
+
+use crate::{
+    transformations,
+    types::{material::Material, matrix::Matrix, mesh::Mesh, sampler::Wrap, vector::Vector},
+};
+
+/// Writes the meshes as a single OBJ file, each mesh as a named group (`g`).
+///
+/// With `bake_transformation` the vertices and normals are written transformed by
+/// `Mesh::transformation_matrix()`, otherwise as stored in the mesh.
+pub fn save_obj(meshes: &[Mesh], bake_transformation: bool) -> String {
+    let mut obj = String::new();
+
+    let mut libraries = Vec::<&str>::new();
+    for library in meshes.iter().flat_map(|mesh| mesh.libraries.iter()) {
+        if !libraries.contains(&library.as_ref()) {
+            libraries.push(library);
+        }
+    }
+    if !libraries.is_empty() {
+        writeln!(obj, "mtllib {}", libraries.join(" ")).unwrap();
+    }
+
+    // OBJ indices are 1-based and shared by the whole file.
+    let (mut vertex_base, mut uv_base, mut normal_base) = (1, 1, 1);
+    for mesh in meshes {
+        let (transformation, normal_transformation, mirrored) = if bake_transformation {
+            let transformation = mesh.transformation_matrix();
+            (
+                transformation.clone(),
+                mesh.normal_transformation_matrix().clone(),
+                transformations::mirrors(transformation),
+            )
+        } else {
+            (Matrix::identity(), Matrix::identity(), false)
+        };
+
+        writeln!(obj, "g {}", mesh.name).unwrap();
+        for vertex in mesh.vertices.iter() {
+            let v = transformation.dot(&[vertex[0], vertex[1], vertex[2], 1.0].into());
+            writeln!(obj, "v {} {} {}", v[0], v[1], v[2]).unwrap();
+        }
+        for uv in mesh.uvs.iter() {
+            writeln!(obj, "vt {} {}", uv[0], uv[1]).unwrap();
+        }
+        for normal in mesh.normals.iter() {
+            let n = normal.transformed_normal(&normal_transformation);
+            writeln!(obj, "vn {} {} {}", n[0], n[1], n[2]).unwrap();
+        }
+
+        for (indices, material) in mesh.material_groups() {
+            writeln!(obj, "usemtl {material}").unwrap();
+            for &(a, b, c) in indices {
+                // Mirroring turns the faces inside out, swapping two corners turns them back.
+                let corners = if mirrored { [a, c, b] } else { [a, b, c] };
+                let corners = corners.map(|(v, uv, n)| {
+                    format!("{}/{}/{}", v + vertex_base, uv + uv_base, n + normal_base)
+                });
+                writeln!(obj, "f {}", corners.join(" ")).unwrap();
+            }
+        }
+
+        vertex_base += mesh.vertices.len();
+        uv_base += mesh.uvs.len();
+        normal_base += mesh.normals.len();
+    }
+    obj
+}
+
+/// Writes the materials as an MTL library.
+pub fn save_mtl<'a>(materials: impl IntoIterator<Item = &'a Material>) -> String {
+    let mut mtl = String::new();
+    for (i, material) in materials.into_iter().enumerate() {
+        if i > 0 {
+            mtl.push('\n');
+        }
+        let color =
+            |name: &str, color: Vector<3>| format!("{name} {} {} {}", color[0], color[1], color[2]);
+        writeln!(mtl, "newmtl {}", material.name).unwrap();
+        writeln!(mtl, "{}", color("Ka", material.ambient)).unwrap();
+        writeln!(mtl, "{}", color("Kd", material.diffuse)).unwrap();
+        writeln!(mtl, "{}", color("Ks", material.specular)).unwrap();
+        writeln!(mtl, "Ns {}", material.shininess).unwrap();
+        writeln!(mtl, "d {}", material.dissolve).unwrap();
+        if let Some(map) = &material.diffuse_map {
+            let options = match (material.sampler.wrap_u, material.sampler.wrap_v) {
+                (Wrap::ClampToEdge, Wrap::ClampToEdge) => "-clamp on ",
+                (Wrap::Repeat, Wrap::Repeat) => "-clamp off ",
+                _ => "",
+            };
+            writeln!(mtl, "map_Kd {options}{map}").unwrap();
+        }
+        if let Some(map) = &material.bump_map {
+            writeln!(mtl, "map_Bump {map}").unwrap();
+        }
+    }
+    mtl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::{load_mtl, load_obj_groups};
+
+    const OBJ: &[u8] = b"mtllib shapes.mtl
+o square
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl red
+f 1/1/1 2/2/1 3/3/1
+usemtl blue
+f 1/1/1 3/3/1 4/4/1
+o triangle
+v 0 0 1
+v 0.5 0 1
+v 0 0.5 1
+vn 0 0 -1
+usemtl red
+f 5//2 7//2 6//2
+";
+
+    #[test]
+    fn test_save_obj_round_trip() {
+        let meshes = load_obj_groups(OBJ).unwrap();
+        let saved = save_obj(&meshes, false);
+        let loaded = load_obj_groups(saved.as_bytes()).unwrap();
+
+        assert_eq!(loaded.len(), meshes.len());
+        for (loaded, mesh) in loaded.iter().zip(meshes.iter()) {
+            assert_eq!(loaded.name, mesh.name);
+            assert_eq!(loaded.vertices, mesh.vertices);
+            assert_eq!(loaded.uvs, mesh.uvs);
+            assert_eq!(loaded.normals, mesh.normals);
+            assert_eq!(loaded.indices, mesh.indices);
+            assert_eq!(loaded.materials, mesh.materials);
+            assert_eq!(loaded.libraries, mesh.libraries);
+        }
+        assert_eq!(saved.matches("mtllib").count(), 1);
+    }
+
+    #[test]
+    fn test_save_obj_baked() {
+        let mut meshes = load_obj_groups(OBJ).unwrap();
+        meshes.truncate(1);
+        meshes[0].translate((1.0, 2.0, 3.0));
+        meshes[0].scale((-2.0, 1.0, 1.0));
+
+        let loaded = load_obj_groups(save_obj(&meshes, true).as_bytes()).unwrap();
+        let square = &loaded[0];
+        let ((a, _, normal), (b, _, _), (c, _, _)) = square.indices[0];
+        let [a, b, c] = [a, b, c].map(|i| square.vertices[i]);
+        assert_eq!(a, (1.0, 2.0, 3.0).into());
+        assert_eq!([b, c], [(-1.0, 3.0, 3.0).into(), (-1.0, 2.0, 3.0).into()]);
+        assert_eq!(square.normals[normal], (0.0, 0.0, 1.0).into());
+        // Mirroring flips the winding back, so the face still points along its normal.
+        assert!((b - a).cross(c - a).dot(square.normals[normal]) > 0.0);
+    }
+
+    #[test]
+    fn test_save_mtl_round_trip() {
+        let materials = load_mtl(
+            b"newmtl red
+Kd 1 0 0
+Ns 10
+d 0.5
+map_Kd -clamp on bricks.png
+
+newmtl blue
+Ka 0 0 0.2
+Kd 0 0 1
+map_Bump bumps.png
+",
+        )
+        .unwrap();
+
+        let loaded = load_mtl(save_mtl(materials.values()).as_bytes()).unwrap();
+        assert_eq!(loaded, materials);
+    }
+}
@@ -1,7 +1,13 @@
 #![feature(test)]
 extern crate test;
 
-pub use loader::{load_image, load_obj};
+use clip::Clipping;
+pub use exporter::{save_mtl, save_obj};
+pub use loader::{
+    AssetError, Gltf, LoadError, LoadOptions, Scene, SceneAssets, SceneCamera, SceneObject,
+    gltf_dependencies, load_gltf, load_image, load_image_with, load_meshes, load_meshes_with,
+    load_mtl, load_obj, load_obj_groups, load_ply, load_scene, load_scene_assets, load_stl,
+};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::sync::mpsc;
@@ -9,15 +15,25 @@ use std::sync::mpsc::channel;
 use types::camera::Camera;
 use types::camera::CameraProperties;
 use types::keys::Key;
+use types::light::Light;
+use types::light::Lighting;
+use types::light::Shading;
+use types::material::Material;
+use types::material::RenderMode;
 use types::mesh::Mesh;
+use types::screen::Antialiasing;
 use types::screen::Screen;
 use types::textures::Image;
 use types::textures::Textures;
 use types::vector;
 use types::vector::Vector;
 
+mod clip;
+mod exporter;
 mod loader;
+pub mod primitives;
 mod rasterize;
+mod render;
 mod transform;
 mod transformations;
 pub mod types;
@@ -31,12 +47,42 @@ pub enum Action {
     AddObject(Box<Mesh>),
     Resize(u32, u32),
     MoveCamera(Vector<3>),
+    SetCamera(Box<Camera>),
     RotateObject(usize, Vector<3>),
+    TranslateObject(usize, Vector<3>),
+    ScaleObject(usize, Vector<3>),
+    /// Sets what is drawn of the objects without a mode of their own.
+    SetRenderMode(RenderMode),
+    /// Switches the objects without a mode of their own to the next mode.
+    CycleRenderMode,
+    /// Sets what is drawn of an object, `None` to follow the mode of the app.
+    SetObjectRenderMode(usize, Option<RenderMode>),
+    AddLight(Light),
+    /// Moves a light, indexed in the order they were added.
+    MoveLight(usize, Vector<3>),
+    SetShading(Shading),
+    /// Switches to the next shading mode.
+    CycleShading,
     End,
 }
 
 pub type Objects = Vec<Mesh>;
 
+/// Actions sent every `interval` milliseconds by the timers, and when the keys are pressed.
+#[derive(Clone, Debug, Default)]
+pub struct Bindings {
+    pub timers: Vec<(u64, Action)>,
+    pub keys: Vec<(Key, Action)>,
+}
+
+/// Settings of the renderer.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub antialiasing: Antialiasing,
+    /// Where the lights are worked out, until changed by an `Action`.
+    pub shading: Shading,
+}
+
 pub trait Interface {
     fn start<
         F: FnMut() -> Option<Screen> + Send + 'static,
@@ -54,31 +100,31 @@ pub trait Interface {
     fn wait(self);
 }
 
-fn setup_screen<I: Interface>() -> (Screen, Camera) {
+fn setup_screen<I: Interface>(antialiasing: Antialiasing) -> (Screen, Camera) {
     let (width, height) = I::get_screen_size();
     let camera_properties = CameraProperties::new(FOV, width as f64 / height as f64, NEAR, FAR);
     let camera = Camera::new(camera_properties);
-    let screen = Screen::new(width, height);
+    let screen = antialiasing.screen(width, height);
     (screen, camera)
 }
 
 pub fn init<'a, I: Interface + 'a>(
     objects: Box<[Mesh]>,
     images: HashMap<Box<str>, Image>,
+    materials: HashMap<Box<str>, Material>,
+    Bindings { timers, keys }: Bindings,
+    Config {
+        antialiasing,
+        mut shading,
+    }: Config,
 ) -> App<I> {
-    let timers = [(10, Action::RotateObject(0, (0.01, 0.02, 0.03).into()))];
-    let keys = [
-        (Key::ArrowUp, Action::MoveCamera((0.0, 0.1, 0.0).into())),
-        (Key::ArrowDown, Action::MoveCamera((0.0, -0.1, 0.0).into())),
-        (Key::ArrowLeft, Action::MoveCamera((-0.1, 0.0, 0.0).into())),
-        (Key::ArrowRight, Action::MoveCamera((0.1, 0.0, 0.0).into())),
-    ];
-
     let (tx, rx) = channel::<Action>();
-    let (mut screen, mut camera) = setup_screen::<I>();
+    let (mut screen, mut camera) = setup_screen::<I>(antialiasing);
 
-    let textures = RefCell::new(Textures::new(images));
+    let textures = RefCell::new(Textures::new(images, materials));
     let mut objects = Vec::from(objects);
+    let mut render_mode = RenderMode::default();
+    let mut lights = Vec::<Light>::new();
 
     let sender = tx.clone();
     let on_resize = move |width, height| {
@@ -110,7 +156,7 @@ pub fn init<'a, I: Interface + 'a>(
                     objects.push(*object);
                 }
                 Action::Resize(width, height) => {
-                    screen = Screen::new(width, height);
+                    screen = antialiasing.screen(width, height);
                     let camera_properties = CameraProperties::inherit(
                         camera.properties().to_owned(),
                         width as f64 / height as f64,
@@ -120,11 +166,54 @@ pub fn init<'a, I: Interface + 'a>(
                 Action::MoveCamera(delta) => {
                     camera.r#move(*delta);
                 }
+                Action::SetCamera(new) => {
+                    // The aspect ratio always follows the screen.
+                    let camera_properties = CameraProperties::inherit(
+                        new.properties().to_owned(),
+                        camera.properties().aspect_ratio(),
+                    );
+                    camera = Camera::inherit(*new, camera_properties);
+                }
                 Action::RotateObject(index, delta) => {
                     if let Some(object) = objects.get_mut(index) {
                         object.rotate(*delta);
                     }
                 }
+                Action::TranslateObject(index, delta) => {
+                    if let Some(object) = objects.get_mut(index) {
+                        object.translate(*delta);
+                    }
+                }
+                Action::ScaleObject(index, factor) => {
+                    if let Some(object) = objects.get_mut(index) {
+                        object.scale(*factor);
+                    }
+                }
+                Action::SetRenderMode(mode) => {
+                    render_mode = mode;
+                }
+                Action::CycleRenderMode => {
+                    render_mode = render_mode.next();
+                }
+                Action::SetObjectRenderMode(index, mode) => {
+                    if let Some(object) = objects.get_mut(index) {
+                        object.render_mode = mode;
+                    }
+                }
+                Action::AddLight(light) => {
+                    lights.push(light);
+                }
+                Action::MoveLight(index, delta) => {
+                    if let Some(light) = lights.get_mut(index) {
+                        light.translate(delta);
+                    }
+                }
+                Action::SetShading(mode) => {
+                    shading = mode;
+                }
+                Action::CycleShading => {
+                    shading = shading.next();
+                }
                 Action::End => {
                     return None;
                 }
@@ -134,18 +223,29 @@ pub fn init<'a, I: Interface + 'a>(
         screen.clear_buffer();
         screen.clear_depth();
 
-        let camera_viewport_transformation =
-            screen.transformation_matrix().clone() * camera.transformation_matrix().clone();
-
         let textures = textures.borrow();
-        let transformed = transform::transform(&textures, &objects, camera_viewport_transformation);
-        for (triangles, texture) in transformed {
-            for p in rasterize::rasterize(texture, triangles) {
-                screen.put_pixel(p.0, p.1, p.2);
-            }
-        }
+        let transformed = transform::transform(
+            &textures,
+            &objects,
+            camera.transformation_matrix().clone(),
+            screen.transformation_matrix().clone(),
+            Clipping::All,
+            render_mode,
+        );
+        let primitives = transformed
+            .flat_map(|(triangles, material, texture, culling, render_mode)| {
+                triangles.map(move |triangle| (triangle, material, texture, culling, render_mode))
+            })
+            .collect::<Vec<_>>();
+        let lighting = Lighting {
+            lights: &lights,
+            eye: camera.position(),
+            shading,
+        };
+        render::render(&mut screen, &primitives, lighting);
 
-        Some(screen.clone())
+        // The interface gets the frame at its size, whatever it was drawn at.
+        Some(antialiasing.resolve(&screen))
     };
 
     let interface = I::start(on_frame, on_resize, timers.to_vec(), keys);
@@ -167,4 +267,33 @@ impl<I: Interface> App<I> {
     pub fn move_camera(&self, delta: Vector<3>) {
         self.1.send(Action::MoveCamera(delta)).unwrap();
     }
+    pub fn set_camera(&self, camera: Camera) {
+        self.1.send(Action::SetCamera(Box::new(camera))).unwrap();
+    }
+    pub fn rotate_object(&self, index: usize, delta: Vector<3>) {
+        self.1.send(Action::RotateObject(index, delta)).unwrap();
+    }
+    pub fn translate_object(&self, index: usize, delta: Vector<3>) {
+        self.1.send(Action::TranslateObject(index, delta)).unwrap();
+    }
+    pub fn scale_object(&self, index: usize, factor: Vector<3>) {
+        self.1.send(Action::ScaleObject(index, factor)).unwrap();
+    }
+    pub fn set_render_mode(&self, mode: RenderMode) {
+        self.1.send(Action::SetRenderMode(mode)).unwrap();
+    }
+    pub fn set_object_render_mode(&self, index: usize, mode: Option<RenderMode>) {
+        self.1
+            .send(Action::SetObjectRenderMode(index, mode))
+            .unwrap();
+    }
+    pub fn add_light(&self, light: Light) {
+        self.1.send(Action::AddLight(light)).unwrap();
+    }
+    pub fn move_light(&self, index: usize, delta: Vector<3>) {
+        self.1.send(Action::MoveLight(index, delta)).unwrap();
+    }
+    pub fn set_shading(&self, shading: Shading) {
+        self.1.send(Action::SetShading(shading)).unwrap();
+    }
 }
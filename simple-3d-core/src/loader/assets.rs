@@ -0,0 +1,205 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt,
+};
+
+use super::{
+    LoadError, Scene, SceneCamera, gltf_dependencies, load_gltf, load_image_with, load_meshes,
+    load_mtl, load_scene,
+};
+use crate::{
+    App, Interface,
+    types::{camera::Camera, material::Material, mesh::Mesh, textures::Image},
+};
+
+/// Scene file together with the models, materials and textures it uses.
+pub struct SceneAssets {
+    pub scene: Scene,
+    pub objects: Box<[Mesh]>,
+    pub images: HashMap<Box<str>, Image>,
+    pub materials: HashMap<Box<str>, Material>,
+    /// Camera of the scene, or of the first glTF file that has one.
+    pub camera: Option<Camera>,
+}
+
+/// File of a scene that could not be loaded.
+#[derive(Debug)]
+pub struct AssetError {
+    pub path: Box<str>,
+    pub error: LoadError,
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.error)
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Loads the scene file at `path` and every file it references, read with `fetch`.
+///
+/// Paths are passed to `fetch` as they are written in the files, it reports files it cannot read
+/// with `LoadError::Fetch`.
+pub async fn load_scene_assets<F, Fut>(path: &str, mut fetch: F) -> Result<SceneAssets, AssetError>
+where
+    F: FnMut(&str) -> Fut,
+    Fut: Future<Output = Result<Box<[u8]>, LoadError>>,
+{
+    let scene = load(&mut fetch, path, load_scene).await?;
+
+    let mut objects = Vec::new();
+    let mut images = HashMap::new();
+    let mut materials = HashMap::new();
+    let mut camera = None;
+    for object in scene.objects.iter() {
+        let path = &*object.model;
+        let meshes = if path.ends_with(".gltf") || path.ends_with(".glb") {
+            let data = fetch(path).await.map_err(failed(path))?;
+            let dependencies = gltf_dependencies(path, &data).map_err(failed(path))?;
+            let mut files = HashMap::new();
+            for dependency in dependencies {
+                let file = fetch(&dependency).await.map_err(failed(&dependency))?;
+                files.insert(dependency, file);
+            }
+            let gltf = load_gltf(path, &data, &files).map_err(failed(path))?;
+            images.extend(gltf.images);
+            materials.extend(gltf.materials);
+            camera = camera.or(gltf.cameras.into_iter().next());
+            gltf.meshes
+        } else {
+            let meshes = load(&mut fetch, path, |data| load_meshes(path, data)).await?;
+            let libraries = meshes.first().map(|mesh| mesh.libraries.clone());
+            for library in libraries.iter().flatten() {
+                materials.extend(load(&mut fetch, library, load_mtl).await?);
+            }
+            meshes
+        };
+        objects.extend(meshes.into_iter().map(|mut mesh| {
+            object.apply(&mut mesh);
+            mesh
+        }));
+    }
+
+    let textures = materials
+        .values()
+        .flat_map(Material::textures)
+        .chain(
+            scene
+                .objects
+                .iter()
+                .filter_map(|object| object.texture.as_deref()),
+        )
+        .map(Box::<str>::from)
+        .collect::<Vec<_>>();
+    for texture in textures {
+        if let Entry::Vacant(entry) = images.entry(texture) {
+            let image = load(&mut fetch, entry.key(), |data| load_image_with(data, true)).await?;
+            entry.insert(image);
+        }
+    }
+
+    let camera = scene.camera.as_ref().map(SceneCamera::camera).or(camera);
+    Ok(SceneAssets {
+        scene,
+        objects: objects.into(),
+        images,
+        materials,
+        camera,
+    })
+}
+
+impl SceneAssets {
+    /// Starts the app with the scene, its camera and lights.
+    pub fn init<I: Interface>(self) -> App<I> {
+        let app = crate::init::<I>(
+            self.objects,
+            self.images,
+            self.materials,
+            self.scene.bindings(),
+            self.scene.config(),
+        );
+        if let Some(camera) = self.camera {
+            app.set_camera(camera);
+        }
+        for light in self.scene.lights() {
+            app.add_light(light);
+        }
+        app
+    }
+}
+
+fn failed(path: &str) -> impl Fn(LoadError) -> AssetError {
+    move |error| AssetError {
+        path: path.into(),
+        error,
+    }
+}
+
+async fn load<T, F, Fut>(
+    fetch: &mut F,
+    path: &str,
+    loader: impl FnOnce(&[u8]) -> Result<T, LoadError>,
+) -> Result<T, AssetError>
+where
+    F: FnMut(&str) -> Fut,
+    Fut: Future<Output = Result<Box<[u8]>, LoadError>>,
+{
+    let data = fetch(path).await.map_err(failed(path))?;
+    loader(&data).map_err(failed(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+
+    fn load_from(files: &[(&str, &str)]) -> Result<SceneAssets, AssetError> {
+        let files = files
+            .iter()
+            .map(|&(path, data)| (path, data.as_bytes()))
+            .collect::<HashMap<_, _>>();
+        let loading = pin!(load_scene_assets("scene.toml", |path| {
+            std::future::ready(
+                files
+                    .get(path)
+                    .map(|&data| data.into())
+                    .ok_or_else(|| LoadError::Fetch("not found".into())),
+            )
+        }));
+        match loading.poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(loaded) => loaded,
+            Poll::Pending => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_load_scene_assets() {
+        let scene = "[[objects]]\nmodel = \"triangle.obj\"\ntranslation = [1.0, 0.0, 0.0]\n";
+        let model = "mtllib triangle.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n";
+        let assets = load_from(&[
+            ("scene.toml", scene),
+            ("triangle.obj", model),
+            ("triangle.mtl", "newmtl red\nKd 1 0 0\n"),
+        ])
+        .unwrap();
+        assert_eq!(assets.objects.len(), 1);
+        assert!(assets.materials.contains_key("red"));
+        assert!(assets.camera.is_none());
+
+        // The file that failed is named, whichever loaded it.
+        let error = load_from(&[("scene.toml", scene), ("triangle.obj", model)])
+            .err()
+            .unwrap();
+        assert_eq!(&*error.path, "triangle.mtl");
+        assert!(matches!(error.error, LoadError::Fetch(_)));
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// Malformed value, `line` and `column` are 1-based.
+    Parse {
+        line: usize,
+        column: usize,
+        message: Box<str>,
+    },
+    /// Malformed binary data, `offset` in bytes from the start of the file.
+    InvalidBinary {
+        offset: usize,
+        message: Box<str>,
+    },
+    UnsupportedDirective {
+        line: usize,
+        directive: Box<str>,
+    },
+    /// Face referencing an element the file does not define, `index` as written in the file.
+    IndexOutOfRange {
+        line: usize,
+        element: &'static str,
+        index: i64,
+        len: usize,
+    },
+    /// Well formed file describing an invalid model, like a reference to an element that does not exist.
+    Invalid(Box<str>),
+    /// File referenced by the model (a buffer or texture) that was not provided to the loader.
+    MissingFile(Box<str>),
+    UnsupportedFormat(Box<str>),
+    /// File that could not be read, with the reason given by whatever was reading it.
+    Fetch(Box<str>),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "parse error at {line}:{column}: {message}"),
+            LoadError::InvalidBinary { offset, message } => {
+                write!(f, "invalid data at byte {offset}: {message}")
+            }
+            LoadError::UnsupportedDirective { line, directive } => {
+                write!(f, "unsupported directive `{directive}` at line {line}")
+            }
+            LoadError::IndexOutOfRange {
+                line,
+                element,
+                index,
+                len,
+            } => write!(
+                f,
+                "{element} index {index} at line {line} is out of range, {len} defined"
+            ),
+            LoadError::Invalid(message) => write!(f, "invalid model: {message}"),
+            LoadError::MissingFile(name) => write!(f, "missing referenced file `{name}`"),
+            LoadError::UnsupportedFormat(name) => write!(f, "unsupported file format of `{name}`"),
+            LoadError::Fetch(reason) => write!(f, "failed to read: {reason}"),
+            LoadError::Image(error) => write!(f, "failed to decode image: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Image(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for LoadError {
+    fn from(error: image::ImageError) -> Self {
+        LoadError::Image(error)
+    }
+}
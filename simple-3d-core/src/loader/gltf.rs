@@ -0,0 +1,1083 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use super::{LoadError, load_image_with, obj::polygon_normal};
+use crate::{
+    FAR, transformations,
+    types::{
+        camera::{Camera, CameraProperties},
+        material::{Blend, FaceCulling, Material},
+        matrix::Matrix,
+        mesh::{Indice, MaterialRange, Mesh},
+        sampler::{Filter, MipFilter, Sampler, Wrap},
+        textures::Image,
+        vector::Vector,
+    },
+};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const TRIANGLES: u32 = 4;
+const TRIANGLE_STRIP: u32 = 5;
+const TRIANGLE_FAN: u32 = 6;
+
+/// Contents of a glTF file, with the node transforms baked into the meshes.
+#[derive(Debug)]
+pub struct Gltf {
+    pub meshes: Vec<Mesh>,
+    pub materials: HashMap<Box<str>, Material>,
+    /// Images embedded in the file, external ones are only referenced by the materials.
+    pub images: HashMap<Box<str>, Image>,
+    /// Perspective cameras placed in the scene, orthographic ones are skipped.
+    pub cameras: Vec<Camera>,
+}
+
+/// Schema of the parts of a glTF document the loader understands.
+mod schema {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, de::IgnoredAny};
+
+    #[derive(Deserialize, Default)]
+    #[serde(default, rename_all = "camelCase")]
+    pub struct Document {
+        pub accessors: Vec<Accessor>,
+        pub buffer_views: Vec<BufferView>,
+        pub buffers: Vec<Buffer>,
+        pub cameras: Vec<Camera>,
+        pub images: Vec<Image>,
+        pub materials: Vec<Material>,
+        pub meshes: Vec<Mesh>,
+        pub nodes: Vec<Node>,
+        pub scene: Option<usize>,
+        pub samplers: Vec<Sampler>,
+        pub scenes: Vec<Scene>,
+        pub textures: Vec<Texture>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Accessor {
+        pub buffer_view: Option<usize>,
+        #[serde(default)]
+        pub byte_offset: usize,
+        pub component_type: u32,
+        #[serde(default)]
+        pub normalized: bool,
+        pub count: usize,
+        #[serde(rename = "type")]
+        pub kind: Box<str>,
+        pub sparse: Option<IgnoredAny>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BufferView {
+        pub buffer: usize,
+        #[serde(default)]
+        pub byte_offset: usize,
+        pub byte_length: usize,
+        pub byte_stride: Option<usize>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Buffer {
+        pub uri: Option<Box<str>>,
+        pub byte_length: usize,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Camera {
+        pub perspective: Option<Perspective>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Perspective {
+        pub aspect_ratio: Option<f64>,
+        pub yfov: f64,
+        pub zfar: Option<f64>,
+        pub znear: f64,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Image {
+        pub uri: Option<Box<str>>,
+        pub buffer_view: Option<usize>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Material {
+        pub name: Option<Box<str>>,
+        pub pbr_metallic_roughness: Option<Pbr>,
+        #[serde(default)]
+        pub double_sided: bool,
+        pub alpha_mode: Option<Box<str>>,
+        pub alpha_cutoff: Option<f64>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Pbr {
+        #[serde(default = "white")]
+        pub base_color_factor: [f64; 4],
+        pub base_color_texture: Option<TextureInfo>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TextureInfo {
+        pub index: usize,
+        #[serde(default)]
+        pub tex_coord: usize,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Mesh {
+        pub name: Option<Box<str>>,
+        pub primitives: Vec<Primitive>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Primitive {
+        pub attributes: HashMap<Box<str>, usize>,
+        pub indices: Option<usize>,
+        pub material: Option<usize>,
+        #[serde(default = "triangles")]
+        pub mode: u32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Node {
+        pub name: Option<Box<str>>,
+        #[serde(default)]
+        pub children: Vec<usize>,
+        pub mesh: Option<usize>,
+        pub camera: Option<usize>,
+        pub matrix: Option<[f64; 16]>,
+        pub translation: Option<[f64; 3]>,
+        pub rotation: Option<[f64; 4]>,
+        pub scale: Option<[f64; 3]>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Scene {
+        #[serde(default)]
+        pub nodes: Vec<usize>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Texture {
+        pub source: Option<usize>,
+        pub sampler: Option<usize>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Sampler {
+        pub mag_filter: Option<u32>,
+        pub min_filter: Option<u32>,
+        #[serde(default = "repeat")]
+        pub wrap_s: u32,
+        #[serde(default = "repeat")]
+        pub wrap_t: u32,
+    }
+
+    fn white() -> [f64; 4] {
+        [1.0; 4]
+    }
+    fn repeat() -> u32 {
+        10497
+    }
+    fn triangles() -> u32 {
+        super::TRIANGLES
+    }
+}
+
+/// External files (buffers) a `.gltf` or `.glb` file needs, to be passed to `load_gltf`.
+/// Paths are relative to the directory of `name`, the same way textures are named.
+pub fn gltf_dependencies(name: &str, data: &[u8]) -> Result<Vec<Box<str>>, LoadError> {
+    let (json, _) = split(data)?;
+    let document = parse(json)?;
+    Ok(document
+        .buffers
+        .iter()
+        .filter_map(|buffer| buffer.uri.as_deref())
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| resolve(name, uri))
+        .collect())
+}
+
+/// Loads the default scene of a `.gltf` or `.glb` file, `files` holding its dependencies by name.
+pub fn load_gltf(
+    name: &str,
+    data: &[u8],
+    files: &HashMap<Box<str>, Box<[u8]>>,
+) -> Result<Gltf, LoadError> {
+    let (json, bin) = split(data)?;
+    let document = parse(json)?;
+    let buffers = buffers(name, &document, bin, files)?;
+    let context = Context {
+        name,
+        document,
+        buffers,
+    };
+
+    let mut gltf = Gltf {
+        meshes: Vec::new(),
+        materials: context.materials()?,
+        images: context.images()?,
+        cameras: Vec::new(),
+    };
+    let mut visited = HashSet::new();
+    for root in context.roots()? {
+        context.visit(root, &Matrix::identity(), &mut visited, &mut gltf)?;
+    }
+    Ok(gltf)
+}
+
+/// Splits a `.glb` container into its JSON and binary chunks, `.gltf` files are plain JSON.
+fn split(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), LoadError> {
+    if data.get(..4) != Some(&GLB_MAGIC.to_le_bytes()) {
+        return Ok((data, None));
+    }
+    let read = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(|| LoadError::InvalidBinary {
+                offset: data.len(),
+                message: "unexpected end of file".into(),
+            })
+    };
+
+    let length = read(8)? as usize;
+    if length > data.len() {
+        return Err(LoadError::InvalidBinary {
+            offset: data.len(),
+            message: format!("expected {length} bytes").into(),
+        });
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset < length {
+        let (chunk_length, kind) = (read(offset)? as usize, read(offset + 4)?);
+        let start = offset + 8;
+        let Some(chunk) = data[..length].get(start..start + chunk_length) else {
+            return Err(LoadError::InvalidBinary {
+                offset,
+                message: format!("chunk of {chunk_length} bytes exceeds the file").into(),
+            });
+        };
+        match kind {
+            CHUNK_JSON if json.is_none() => json = Some(chunk),
+            CHUNK_BIN if bin.is_none() => bin = Some(chunk),
+            // Unknown chunks have to be skipped, as required by the spec.
+            _ => {}
+        }
+        offset = start + chunk_length;
+    }
+
+    let json = json.ok_or_else(|| LoadError::InvalidBinary {
+        offset: 12,
+        message: "missing JSON chunk".into(),
+    })?;
+    Ok((json, bin))
+}
+
+fn parse(json: &[u8]) -> Result<schema::Document, LoadError> {
+    serde_json::from_slice(json).map_err(|error| {
+        let message = error.to_string();
+        // The position is already part of the error, keep only the description.
+        let message = message.split(" at line ").next().unwrap_or(&message);
+        LoadError::Parse {
+            line: error.line(),
+            column: error.column(),
+            message: message.into(),
+        }
+    })
+}
+
+fn buffers<'a>(
+    name: &str,
+    document: &schema::Document,
+    bin: Option<&'a [u8]>,
+    files: &'a HashMap<Box<str>, Box<[u8]>>,
+) -> Result<Vec<Cow<'a, [u8]>>, LoadError> {
+    document
+        .buffers
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| {
+            let data = match buffer.uri.as_deref() {
+                None => Cow::Borrowed(bin.ok_or_else(|| {
+                    LoadError::Invalid(
+                        format!("buffer {index} has neither uri nor GLB chunk").into(),
+                    )
+                })?),
+                Some(uri) => match data_uri(uri) {
+                    Some(data) => Cow::Owned(data?),
+                    None => {
+                        let path = resolve(name, uri);
+                        match files.get(&path) {
+                            Some(data) => Cow::Borrowed(data.as_ref()),
+                            None => return Err(LoadError::MissingFile(path)),
+                        }
+                    }
+                },
+            };
+            if data.len() < buffer.byte_length {
+                return Err(LoadError::Invalid(
+                    format!(
+                        "buffer {index} has {} bytes, {} expected",
+                        data.len(),
+                        buffer.byte_length
+                    )
+                    .into(),
+                ));
+            }
+            Ok(data)
+        })
+        .collect()
+}
+
+/// Path of `uri` relative to the directory of the file `name`.
+fn resolve(name: &str, uri: &str) -> Box<str> {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = uri
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let uri = String::from_utf8_lossy(&decoded);
+    match name.rsplit_once('/') {
+        Some((directory, _)) => format!("{directory}/{uri}").into(),
+        None => uri.into(),
+    }
+}
+
+/// Decodes a base64 `data:` URI, `None` if `uri` refers to a file instead.
+fn data_uri(uri: &str) -> Option<Result<Vec<u8>, LoadError>> {
+    let data = uri.strip_prefix("data:")?;
+    let Some((_, payload)) = data.split_once(";base64,") else {
+        return Some(Err(LoadError::Invalid(
+            "only base64 encoded data URIs are supported".into(),
+        )));
+    };
+    Some(base64(payload))
+}
+
+fn base64(text: &str) -> Result<Vec<u8>, LoadError> {
+    #[inline]
+    fn value(c: u8) -> Option<u32> {
+        Some(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        } as u32)
+    }
+
+    let text = text.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = value(c).ok_or_else(|| {
+            LoadError::Invalid(format!("invalid base64 character `{}`", c as char).into())
+        })?;
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+fn get<'a, T>(items: &'a [T], index: usize, what: &str) -> Result<&'a T, LoadError> {
+    items
+        .get(index)
+        .ok_or_else(|| LoadError::Invalid(format!("{what} {index} does not exist").into()))
+}
+
+/// Transformation of a node relative to its parent.
+fn local_transformation(node: &schema::Node) -> Matrix<4, 4> {
+    use std::array::from_fn;
+
+    if let Some(matrix) = node.matrix {
+        // Stored in column major order.
+        return from_fn(|row| from_fn(|column| matrix[column * 4 + row])).into();
+    }
+    transformations::translate(node.translation.unwrap_or([0.0; 3]))
+        * transformations::rotate_quaternion(node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]))
+        * transformations::scale(node.scale.unwrap_or([1.0; 3]))
+}
+
+/// Inverse transpose of the linear part of `transformation` up to scale, for transforming normals,
+/// and whether the transformation mirrors, flipping the winding of faces.
+fn normal_transformation(transformation: &Matrix<4, 4>) -> (Matrix<4, 4>, bool) {
+    let column = |i: usize| {
+        let v = transformation.dot(&Vector::from(std::array::from_fn(|j| {
+            (i == j) as u8 as f64
+        })));
+        Vector::from([v[0], v[1], v[2]])
+    };
+    let (a, b, c) = (column(0), column(1), column(2));
+    let mirrored = transformations::mirrors(transformation);
+    let sign = if mirrored { -1.0 } else { 1.0 };
+    let [x, y, z] = [b.cross(c) * sign, c.cross(a) * sign, a.cross(b) * sign];
+    let matrix = [
+        [x[0], y[0], z[0], 0.0],
+        [x[1], y[1], z[1], 0.0],
+        [x[2], y[2], z[2], 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    (matrix.into(), mirrored)
+}
+
+/// Byte size, reader and the maximum used to normalize values of an accessor component type.
+type Component = (usize, fn(&[u8]) -> f64, f64);
+
+fn component(kind: u32) -> Option<Component> {
+    Some(match kind {
+        5120 => (1, |b| i8::from_le_bytes([b[0]]) as f64, i8::MAX as f64),
+        5121 => (1, |b| b[0] as f64, u8::MAX as f64),
+        5122 => (
+            2,
+            |b| i16::from_le_bytes([b[0], b[1]]) as f64,
+            i16::MAX as f64,
+        ),
+        5123 => (
+            2,
+            |b| u16::from_le_bytes([b[0], b[1]]) as f64,
+            u16::MAX as f64,
+        ),
+        5125 => (4, |b| u32::from_le_bytes(b.try_into().unwrap()) as f64, 1.0),
+        5126 => (4, |b| f32::from_le_bytes(b.try_into().unwrap()) as f64, 1.0),
+        _ => return None,
+    })
+}
+
+struct Context<'a> {
+    name: &'a str,
+    document: schema::Document,
+    buffers: Vec<Cow<'a, [u8]>>,
+}
+
+impl Context<'_> {
+    fn view(&self, index: usize) -> Result<(&[u8], Option<usize>), LoadError> {
+        let view = get(&self.document.buffer_views, index, "buffer view")?;
+        let buffer = get(&self.buffers, view.buffer, "buffer")?;
+        let data = buffer
+            .get(view.byte_offset..view.byte_offset + view.byte_length)
+            .ok_or_else(|| {
+                LoadError::Invalid(format!("buffer view {index} exceeds its buffer").into())
+            })?;
+        Ok((data, view.byte_stride))
+    }
+
+    /// Values of an accessor with the number of components per element.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), LoadError> {
+        let accessor = get(&self.document.accessors, index, "accessor")?;
+        let invalid =
+            |message: &str| LoadError::Invalid(format!("accessor {index} {message}").into());
+
+        let size = match &*accessor.kind {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            kind => return Err(invalid(&format!("has unsupported type `{kind}`"))),
+        };
+        if accessor.sparse.is_some() {
+            return Err(invalid("is sparse, which is not supported"));
+        }
+        let Some((component_size, read, max)) = component(accessor.component_type) else {
+            return Err(invalid("has an unknown component type"));
+        };
+        let Some(view) = accessor.buffer_view else {
+            // Accessors without a buffer view are all zeros.
+            return Ok((vec![0.0; accessor.count * size], size));
+        };
+
+        let (data, stride) = self.view(view)?;
+        let element = component_size * size;
+        let stride = stride.unwrap_or(element);
+        if accessor.count > 0
+            && accessor.byte_offset + stride * (accessor.count - 1) + element > data.len()
+        {
+            return Err(invalid("exceeds its buffer view"));
+        }
+
+        let values = (0..accessor.count)
+            .flat_map(|i| {
+                let start = accessor.byte_offset + i * stride;
+                (0..size).map(move |j| start + j * component_size)
+            })
+            .map(|offset| {
+                let value = read(&data[offset..offset + component_size]);
+                if accessor.normalized {
+                    (value / max).max(-1.0)
+                } else {
+                    value
+                }
+            })
+            .collect();
+        Ok((values, size))
+    }
+
+    /// Per vertex attribute of `count` elements with `S` components each.
+    fn attribute<const S: usize>(
+        &self,
+        index: usize,
+        count: usize,
+    ) -> Result<Vec<Vector<S>>, LoadError> {
+        let (values, size) = self.accessor(index)?;
+        if size != S || values.len() != count * S {
+            return Err(LoadError::Invalid(
+                format!("accessor {index} does not hold {count} elements of {S} components").into(),
+            ));
+        }
+        Ok(values
+            .chunks_exact(S)
+            .map(|chunk| chunk.iter().copied().collect())
+            .collect())
+    }
+
+    /// Corners of the triangles of a primitive, indexing its `count` vertices.
+    fn triangles(
+        &self,
+        primitive: &schema::Primitive,
+        count: usize,
+    ) -> Result<Vec<[usize; 3]>, LoadError> {
+        let indices = match primitive.indices {
+            Some(accessor) => self
+                .accessor(accessor)?
+                .0
+                .into_iter()
+                .map(|index| index as usize)
+                .collect::<Vec<_>>(),
+            None => (0..count).collect(),
+        };
+        if let Some(index) = indices.iter().find(|&&index| index >= count) {
+            return Err(LoadError::Invalid(
+                format!("vertex index {index} is out of range, {count} defined").into(),
+            ));
+        }
+
+        Ok(match primitive.mode {
+            TRIANGLE_STRIP => indices
+                .windows(3)
+                .enumerate()
+                // Every other triangle of a strip has the opposite winding.
+                .map(|(i, t)| match i % 2 {
+                    0 => [t[0], t[1], t[2]],
+                    _ => [t[1], t[0], t[2]],
+                })
+                .collect(),
+            TRIANGLE_FAN => (1..indices.len().saturating_sub(1))
+                .map(|i| [indices[0], indices[i], indices[i + 1]])
+                .collect(),
+            _ => indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+        })
+    }
+
+    fn material_name(&self, index: usize) -> Box<str> {
+        self.document
+            .materials
+            .get(index)
+            .and_then(|material| material.name.clone())
+            .unwrap_or_else(|| format!("{}#material{index}", self.name).into())
+    }
+
+    /// Name external images are loaded by, embedded ones are named after the file they are in.
+    fn image_name(&self, index: usize) -> Box<str> {
+        match self
+            .document
+            .images
+            .get(index)
+            .and_then(|image| image.uri.as_deref())
+        {
+            Some(uri) if !uri.starts_with("data:") => resolve(self.name, uri),
+            _ => format!("{}#image{index}", self.name).into(),
+        }
+    }
+
+    fn sampler(&self, index: usize) -> Result<Sampler, LoadError> {
+        let sampler = get(&self.document.samplers, index, "sampler")?;
+        let wrap = |mode: u32| match mode {
+            33071 => Ok(Wrap::ClampToEdge),
+            33648 => Ok(Wrap::MirroredRepeat),
+            10497 => Ok(Wrap::Repeat),
+            _ => Err(LoadError::Invalid(
+                format!("sampler {index} has unknown wrap mode {mode}").into(),
+            )),
+        };
+        Ok(Sampler {
+            // Only magnification picks the texel filter, minification picks how mip levels are used.
+            filter: match sampler.mag_filter {
+                Some(9728) => Filter::Nearest,
+                _ => Filter::Bilinear,
+            },
+            mipmap: match sampler.min_filter {
+                Some(9728 | 9729) => MipFilter::None,
+                Some(9984 | 9986) => MipFilter::Nearest,
+                _ => MipFilter::Linear,
+            },
+            wrap_u: wrap(sampler.wrap_s)?,
+            wrap_v: wrap(sampler.wrap_t)?,
+        })
+    }
+
+    fn materials(&self) -> Result<HashMap<Box<str>, Material>, LoadError> {
+        let mut materials = HashMap::new();
+        for (index, material) in self.document.materials.iter().enumerate() {
+            let name = self.material_name(index);
+            let mut result = Material::new(&name);
+            if material.double_sided {
+                result.culling = FaceCulling::NONE;
+            }
+            match material.alpha_mode.as_deref() {
+                None | Some("OPAQUE") => {}
+                Some("MASK") => result.alpha_cutoff = Some(material.alpha_cutoff.unwrap_or(0.5)),
+                Some("BLEND") => result.blend = Blend::Alpha,
+                Some(mode) => {
+                    return Err(LoadError::Invalid(
+                        format!("material {index} has unknown alpha mode {mode}").into(),
+                    ));
+                }
+            }
+            if let Some(pbr) = &material.pbr_metallic_roughness {
+                let [r, g, b, a] = pbr.base_color_factor;
+                result.diffuse = (r, g, b).into();
+                result.dissolve = a;
+                if let Some(info) = &pbr.base_color_texture {
+                    let texture = get(&self.document.textures, info.index, "texture")?;
+                    let source = texture.source.ok_or_else(|| {
+                        LoadError::Invalid(format!("texture {} has no image", info.index).into())
+                    })?;
+                    get(&self.document.images, source, "image")?;
+                    result.diffuse_map = Some(self.image_name(source));
+                    // Textures without a sampler repeat, as the spec asks.
+                    result.sampler = match texture.sampler {
+                        Some(sampler) => self.sampler(sampler)?,
+                        None => Sampler::new(Filter::default(), Wrap::Repeat),
+                    };
+                }
+            }
+            materials.insert(name, result);
+        }
+        Ok(materials)
+    }
+
+    /// Decodes the images stored inside the file, in data URIs or buffer views.
+    fn images(&self) -> Result<HashMap<Box<str>, Image>, LoadError> {
+        let mut images = HashMap::new();
+        for (index, image) in self.document.images.iter().enumerate() {
+            let data = match (image.uri.as_deref().and_then(data_uri), image.buffer_view) {
+                (Some(data), _) => Cow::Owned(data?),
+                (None, Some(view)) => Cow::Borrowed(self.view(view)?.0),
+                (None, None) => continue,
+            };
+            images.insert(self.image_name(index), load_image_with(&data, true)?);
+        }
+        Ok(images)
+    }
+
+    fn roots(&self) -> Result<Vec<usize>, LoadError> {
+        let scene = self
+            .document
+            .scene
+            .or((!self.document.scenes.is_empty()).then_some(0));
+        if let Some(scene) = scene {
+            return Ok(get(&self.document.scenes, scene, "scene")?.nodes.clone());
+        }
+        // Without scenes, every node that is not a child of another one is a root.
+        let children = self
+            .document
+            .nodes
+            .iter()
+            .flat_map(|node| node.children.iter().copied())
+            .collect::<HashSet<_>>();
+        Ok((0..self.document.nodes.len())
+            .filter(|index| !children.contains(index))
+            .collect())
+    }
+
+    fn visit(
+        &self,
+        index: usize,
+        parent: &Matrix<4, 4>,
+        visited: &mut HashSet<usize>,
+        gltf: &mut Gltf,
+    ) -> Result<(), LoadError> {
+        let node = get(&self.document.nodes, index, "node")?;
+        if !visited.insert(index) {
+            return Err(LoadError::Invalid(
+                format!("node {index} has more than one parent").into(),
+            ));
+        }
+        let transformation = parent.clone() * local_transformation(node);
+
+        if let Some(mesh) = node.mesh {
+            gltf.meshes
+                .push(self.mesh(mesh, node.name.as_deref(), &transformation)?);
+        }
+        if let Some(camera) = node.camera {
+            let camera = get(&self.document.cameras, camera, "camera")?;
+            if let Some(perspective) = &camera.perspective {
+                gltf.cameras.push(camera_at(perspective, &transformation));
+            }
+        }
+        for &child in &node.children {
+            self.visit(child, &transformation, visited, gltf)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(
+        &self,
+        index: usize,
+        name: Option<&str>,
+        transformation: &Matrix<4, 4>,
+    ) -> Result<Mesh, LoadError> {
+        let mesh = get(&self.document.meshes, index, "mesh")?;
+        let (normal_transformation, mirrored) = normal_transformation(transformation);
+
+        let mut vertices = Vec::<Vector<3>>::new();
+        let mut uvs = Vec::<Vector<2>>::new();
+        let mut normals = Vec::<Vector<3>>::new();
+        let mut colors = Vec::<Vector<4>>::new();
+        let mut has_colors = false;
+        let mut indices = Vec::<Indice>::new();
+        let mut materials = Vec::<MaterialRange>::new();
+
+        for primitive in &mesh.primitives {
+            // Points and lines have no faces to rasterize.
+            if !matches!(primitive.mode, TRIANGLES | TRIANGLE_STRIP | TRIANGLE_FAN) {
+                continue;
+            }
+            let attribute = |name: &str| primitive.attributes.get(name).copied();
+            let Some(positions) = attribute("POSITION") else {
+                return Err(LoadError::Invalid(
+                    format!("primitive of mesh {index} has no positions").into(),
+                ));
+            };
+            let count = get(&self.document.accessors, positions, "accessor")?.count;
+            let positions = self.attribute::<3>(positions, count)?;
+
+            let vertex_base = vertices.len();
+            vertices.extend(positions.iter().map(|position| {
+                let v = transformation.dot(&[position[0], position[1], position[2], 1.0].into());
+                Vector::from([v[0], v[1], v[2]])
+            }));
+
+            let tex_coord = primitive
+                .material
+                .and_then(|material| self.document.materials.get(material))
+                .and_then(|material| material.pbr_metallic_roughness.as_ref())
+                .and_then(|pbr| pbr.base_color_texture.as_ref())
+                .map_or(0, |info| info.tex_coord);
+            let uv_base = uvs.len();
+            let per_vertex_uvs = match attribute(&format!("TEXCOORD_{tex_coord}")) {
+                Some(accessor) => {
+                    uvs.extend(self.attribute::<2>(accessor, count)?);
+                    true
+                }
+                None => {
+                    uvs.push((0.0, 0.0).into());
+                    false
+                }
+            };
+
+            let normal_base = normals.len();
+            let per_vertex_normals = match attribute("NORMAL") {
+                Some(accessor) => {
+                    normals.extend(
+                        self.attribute::<3>(accessor, count)?
+                            .iter()
+                            .map(|normal| normal.transformed_normal(&normal_transformation)),
+                    );
+                    true
+                }
+                None => false,
+            };
+
+            match attribute("COLOR_0") {
+                Some(accessor) => {
+                    let (values, size) = self.accessor(accessor)?;
+                    if !matches!(size, 3 | 4) || values.len() != count * size {
+                        return Err(LoadError::Invalid(
+                            format!("accessor {accessor} does not hold {count} colors").into(),
+                        ));
+                    }
+                    colors.extend(values.chunks_exact(size).map(|color| {
+                        Vector::from([color[0], color[1], color[2], *color.get(3).unwrap_or(&1.0)])
+                    }));
+                    has_colors = true;
+                }
+                None => colors.extend((0..count).map(|_| Vector::from([1.0; 4]))),
+            }
+
+            let start = indices.len();
+            for [a, b, c] in self.triangles(primitive, count)? {
+                let [a, b, c] = if mirrored { [a, c, b] } else { [a, b, c] };
+                // Without normals the faces are flat shaded, as the spec requires.
+                let flat_normal = (!per_vertex_normals).then(|| {
+                    let points = [a, b, c].map(|i| vertices[vertex_base + i]);
+                    normals.push(polygon_normal(&points));
+                    normals.len() - 1
+                });
+                let corner = |i: usize| {
+                    (
+                        vertex_base + i,
+                        if per_vertex_uvs { uv_base + i } else { uv_base },
+                        flat_normal.unwrap_or(normal_base + i),
+                    )
+                };
+                indices.push((corner(a), corner(b), corner(c)));
+            }
+            let material = primitive
+                .material
+                .map_or_else(|| "none".into(), |material| self.material_name(material));
+            materials.push((start..indices.len(), material));
+        }
+
+        let mut result = Mesh::new(vertices, uvs, normals, &indices, &materials);
+        if let Some(name) = name.or(mesh.name.as_deref()) {
+            result.name = name.into();
+        }
+        if has_colors {
+            result.colors = colors.into();
+        }
+        Ok(result)
+    }
+}
+
+fn camera_at(perspective: &schema::Perspective, transformation: &Matrix<4, 4>) -> Camera {
+    let properties = CameraProperties::new(
+        perspective.yfov,
+        perspective.aspect_ratio.unwrap_or(1.0),
+        perspective.znear,
+        perspective.zfar.unwrap_or(FAR),
+    );
+    let direction = |v: [f64; 4]| {
+        let v = transformation.dot(&v.into());
+        Vector::from([v[0], v[1], v[2]])
+    };
+    // glTF cameras look down their local -Z axis, with +Y up.
+    let position = direction([0.0, 0.0, 0.0, 1.0]);
+    let forward = direction([0.0, 0.0, -1.0, 0.0]).normalize();
+    let up = direction([0.0, 1.0, 0.0, 0.0]);
+    Camera::looking_at(properties, position, position + forward, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"{
+  "asset": { "version": "2.0" },
+  "scene": 0,
+  "scenes": [{ "nodes": [0, 1] }],
+  "nodes": [
+    { "name": "triangle", "mesh": 0, "translation": [1, 2, 3], "children": [2] },
+    { "camera": 0, "translation": [0, 0, 10] },
+    { "mesh": 0, "scale": [2, 2, 2] }
+  ],
+  "cameras": [{ "type": "perspective", "perspective": { "yfov": 0.8, "znear": 0.5, "zfar": 50 } }],
+  "meshes": [{ "name": "mesh", "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] }],
+  "materials": [{ "name": "red", "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 0.5], "baseColorTexture": { "index": 0 } }, "alphaMode": "BLEND" }],
+  "textures": [{ "source": 0, "sampler": 0 }],
+  "samplers": [{ "magFilter": 9728, "minFilter": 9984, "wrapS": 33071 }],
+  "images": [{ "uri": "textures/red%20brick.png" }],
+  "accessors": [
+    { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+    { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+  ],
+  "bufferViews": [
+    { "buffer": 0, "byteLength": 36 },
+    { "buffer": 0, "byteOffset": 36, "byteLength": 6 }
+  ],
+  "buffers": [{ BUFFER"byteLength": 42 }]
+}"#;
+    /// Triangle `(0, 0, 0), (1, 0, 0), (0, 1, 0)` followed by the `u16` indices `0, 1, 2`.
+    const BUFFER: &str = "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIA";
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let mut data = Vec::new();
+        data.extend(GLB_MAGIC.to_le_bytes());
+        data.extend(2u32.to_le_bytes());
+        data.extend(((28 + json.len() + bin.len()) as u32).to_le_bytes());
+        data.extend((json.len() as u32).to_le_bytes());
+        data.extend(CHUNK_JSON.to_le_bytes());
+        data.extend(json);
+        data.extend((bin.len() as u32).to_le_bytes());
+        data.extend(CHUNK_BIN.to_le_bytes());
+        data.extend(bin);
+        data
+    }
+
+    fn assert_scene(gltf: &Gltf) {
+        assert_eq!(gltf.meshes.len(), 2);
+        let triangle = &gltf.meshes[0];
+        assert_eq!(&*triangle.name, "triangle");
+        assert_eq!(
+            &*triangle.vertices,
+            &[
+                Vector::from([1.0, 2.0, 3.0]),
+                Vector::from([2.0, 2.0, 3.0]),
+                Vector::from([1.0, 3.0, 3.0])
+            ]
+        );
+        assert_eq!(&*triangle.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+        assert_eq!(&*triangle.indices, &[((0, 0, 0), (1, 0, 0), (2, 0, 0))]);
+        assert_eq!(&*triangle.materials, &[(0..1, "red".into())]);
+
+        // The child node inherits the translation of its parent.
+        let child = &gltf.meshes[1];
+        assert_eq!(&*child.name, "mesh");
+        assert_eq!(child.vertices[1], Vector::from([3.0, 2.0, 3.0]));
+
+        let red = &gltf.materials["red"];
+        assert_eq!(red.diffuse, (1.0, 0.0, 0.0).into());
+        assert_eq!(red.dissolve, 0.5);
+        assert_eq!(red.blend, Blend::Alpha);
+        assert_eq!(
+            red.diffuse_map.as_deref(),
+            Some("models/textures/red brick.png")
+        );
+        assert_eq!(
+            red.sampler,
+            Sampler {
+                filter: Filter::Nearest,
+                mipmap: MipFilter::Nearest,
+                wrap_u: Wrap::ClampToEdge,
+                wrap_v: Wrap::Repeat,
+            }
+        );
+
+        let [camera] = &gltf.cameras[..] else {
+            panic!("expected a single camera");
+        };
+        assert_eq!(camera.position(), (0.0, 0.0, 10.0).into());
+        assert_eq!(camera.target(), (0.0, 0.0, 9.0).into());
+        assert_eq!(camera.properties().fov(), 0.8);
+        assert_eq!(camera.properties().far(), 50.0);
+    }
+
+    #[test]
+    fn test_load_gltf() {
+        let document = DOCUMENT.replace("BUFFER", "\"uri\": \"triangle.bin\", ");
+        let name = "models/scene.gltf";
+        assert_eq!(
+            gltf_dependencies(name, document.as_bytes()).unwrap(),
+            vec!["models/triangle.bin".into()]
+        );
+
+        let files = HashMap::from([("models/triangle.bin".into(), base64(BUFFER).unwrap().into())]);
+        let gltf = load_gltf(name, document.as_bytes(), &files).unwrap();
+        assert_scene(&gltf);
+
+        assert!(matches!(
+            load_gltf(name, document.as_bytes(), &HashMap::new()),
+            Err(LoadError::MissingFile(file)) if &*file == "models/triangle.bin"
+        ));
+    }
+
+    #[test]
+    fn test_load_gltf_embedded() {
+        let name = "models/scene.gltf";
+        let document = DOCUMENT.replace(
+            "BUFFER",
+            &format!("\"uri\": \"data:application/octet-stream;base64,{BUFFER}\", "),
+        );
+        assert!(
+            gltf_dependencies(name, document.as_bytes())
+                .unwrap()
+                .is_empty()
+        );
+        assert_scene(&load_gltf(name, document.as_bytes(), &HashMap::new()).unwrap());
+
+        let data = glb(&DOCUMENT.replace("BUFFER", ""), &base64(BUFFER).unwrap());
+        assert_scene(&load_gltf("models/scene.glb", &data, &HashMap::new()).unwrap());
+    }
+
+    #[test]
+    fn test_load_gltf_mirrored() {
+        let document = r#"{
+  "nodes": [{ "mesh": 0, "scale": [-1, 1, 1] }],
+  "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+  "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }],
+  "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+  "buffers": [{ "byteLength": 36 }]
+}"#;
+        let gltf = load_gltf(
+            "mirrored.glb",
+            &glb(document, &base64(BUFFER).unwrap()),
+            &HashMap::new(),
+        )
+        .unwrap();
+        let mesh = &gltf.meshes[0];
+        // Mirroring flips the winding back, so the face keeps pointing the same way.
+        assert_eq!(&*mesh.indices, &[((0, 0, 0), (2, 0, 0), (1, 0, 0))]);
+        assert_eq!(&*mesh.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+        assert_eq!(&*mesh.materials, &[(0..1, "none".into())]);
+    }
+
+    #[test]
+    fn test_load_gltf_errors() {
+        let error = load_gltf("broken.gltf", b"{\n  \"nodes\": [}", &HashMap::new()).unwrap_err();
+        assert!(matches!(error, LoadError::Parse { line: 2, .. }));
+
+        let error = load_gltf(
+            "broken.gltf",
+            br#"{ "nodes": [{ "mesh": 3 }] }"#,
+            &HashMap::new(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, LoadError::Invalid(message) if &*message == "mesh 3 does not exist")
+        );
+
+        let mut data = glb("{}", &[]);
+        data.truncate(20);
+        assert!(matches!(
+            load_gltf("broken.glb", &data, &HashMap::new()),
+            Err(LoadError::InvalidBinary { .. })
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    str::{FromStr, SplitAsciiWhitespace},
+};
+
+use crate::types::{mesh::Mesh, pixel::Pixel, textures::Image, validation::RepairOptions};
+
+pub use assets::{AssetError, SceneAssets, load_scene_assets};
+pub use error::LoadError;
+pub use gltf::{Gltf, gltf_dependencies, load_gltf};
+pub use mtl::load_mtl;
+pub use obj::{load_obj, load_obj_groups};
+pub use ply::load_ply;
+pub use scene::{Scene, SceneCamera, SceneObject, load_scene};
+pub use stl::load_stl;
+
+mod assets;
+mod error;
+mod gltf;
+mod mtl;
+mod obj;
+mod ply;
+mod scene;
+mod stl;
+
+/// What `load_meshes_with` does with the meshes after loading them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadOptions {
+    /// Repairs every mesh with `Mesh::repair`, off by default.
+    pub repair: Option<RepairOptions>,
+}
+
+/// Loads the meshes of a model file, picking the loader from the file extension.
+pub fn load_meshes(name: &str, data: &[u8]) -> Result<Vec<Mesh>, LoadError> {
+    load_meshes_with(name, data, &LoadOptions::default())
+}
+
+/// Loads the meshes of a model file like `load_meshes`, post-processing them as set in `options`.
+pub fn load_meshes_with(
+    name: &str,
+    data: &[u8],
+    options: &LoadOptions,
+) -> Result<Vec<Mesh>, LoadError> {
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    let mut meshes = match extension.as_deref() {
+        Some("obj") => load_obj_groups(data),
+        Some("stl") => load_stl(data).map(|mesh| vec![mesh]),
+        Some("ply") => load_ply(data).map(|mesh| vec![mesh]),
+        // Only self-contained files, external buffers have to be loaded with `load_gltf`.
+        Some("gltf" | "glb") => load_gltf(name, data, &HashMap::new()).map(|gltf| gltf.meshes),
+        _ => Err(LoadError::UnsupportedFormat(name.into())),
+    }?;
+    if let Some(repair) = &options.repair {
+        for mesh in meshes.iter_mut() {
+            mesh.repair(repair);
+        }
+    }
+    Ok(meshes)
+}
+
+pub fn load_image(data: &[u8]) -> Result<Image, LoadError> {
+    load_image_with(data, false)
+}
+
+/// Loads an image like `load_image`, building its mip chain when `mipmaps` is set.
+pub fn load_image_with(data: &[u8], mipmaps: bool) -> Result<Image, LoadError> {
+    use image::{GenericImageView, load_from_memory};
+
+    let image = load_from_memory(data)?;
+    let pixels = image
+        .pixels()
+        .map(|(_, _, rgba)| rgba.into())
+        .collect::<Box<[Pixel]>>();
+    let image = Image::load(image.width(), image.height(), &pixels);
+    Ok(if mipmaps { image.with_mipmaps() } else { image })
+}
+
+/// Whitespace separated tokens of a single line of a text format, keeping track of the position for errors.
+struct Tokens<'a> {
+    line: usize,
+    text: &'a str,
+    parts: SplitAsciiWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: usize, text: &'a str) -> Self {
+        Self {
+            line,
+            text,
+            parts: text.split_ascii_whitespace(),
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.line
+    }
+    /// Everything after the directive, for values that may contain whitespace.
+    fn arguments(&self) -> &'a str {
+        let text = self.text.trim_start();
+        let directive_end = text.find(|c: char| c.is_ascii_whitespace());
+        text[directive_end.unwrap_or(text.len())..].trim()
+    }
+
+    fn expect(&mut self, what: &str) -> Result<&'a str, LoadError> {
+        self.parts.next().ok_or_else(|| LoadError::Parse {
+            line: self.line,
+            column: self.text.trim_end().len() + 1,
+            message: format!("expected {what}").into(),
+        })
+    }
+    fn parse<T: FromStr>(&mut self, what: &str) -> Result<T, LoadError> {
+        let token = self.expect(what)?;
+        self.parse_token(token, what)
+    }
+    fn parse_token<T: FromStr>(&self, token: &str, what: &str) -> Result<T, LoadError> {
+        token
+            .parse::<T>()
+            .map_err(|_| self.error(token, format!("invalid {what} `{token}`")))
+    }
+
+    /// Error pointing at `token`, which has to be a slice of this line.
+    fn error(&self, token: &str, message: impl Into<Box<str>>) -> LoadError {
+        let column = (token.as_ptr() as usize).saturating_sub(self.text.as_ptr() as usize) + 1;
+        LoadError::Parse {
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parts.next()
+    }
+}
+
+/// Lines of a text file numbered from 1, with comments stripped.
+fn lines(file: &str) -> impl Iterator<Item = Tokens<'_>> {
+    file.lines()
+        .enumerate()
+        .map(|(index, line)| Tokens::new(index + 1, line.split('#').next().unwrap_or("")))
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use super::{LoadError, Tokens, lines};
+use crate::types::{
+    material::{Blend, Material},
+    sampler::Wrap,
+    vector::Vector,
+};
+
+pub fn load_mtl(data: &[u8]) -> Result<HashMap<Box<str>, Material>, LoadError> {
+    #[inline]
+    fn color(tokens: &mut Tokens) -> Result<Vector<3>, LoadError> {
+        let r = tokens.parse::<f64>("color")?;
+        let Some(token) = tokens.next() else {
+            // A single value sets all channels, as allowed by the spec.
+            return Ok((r, r, r).into());
+        };
+        let g = tokens.parse_token::<f64>(token, "color")?;
+        let b = tokens.parse::<f64>("color")?;
+        Ok((r, g, b).into())
+    }
+    /// File name of a map and whether its `-clamp` option is on, if it has one.
+    #[inline]
+    fn map(tokens: &mut Tokens) -> Result<(Box<str>, Option<bool>), LoadError> {
+        // Map options (`-s 1 1 1`, `-bm 0.5`, ...) come before the file name.
+        let arguments = std::iter::once(tokens.expect("texture file name")?)
+            .chain(tokens)
+            .collect::<Vec<_>>();
+        let clamp = arguments
+            .windows(2)
+            .find(|pair| pair[0] == "-clamp")
+            .map(|pair| pair[1] == "on");
+        Ok((arguments[arguments.len() - 1].into(), clamp))
+    }
+
+    let file = String::from_utf8_lossy(data);
+
+    let mut materials = HashMap::<Box<str>, Material>::new();
+    let mut current = None::<Material>;
+
+    for mut tokens in lines(&file) {
+        let directive = tokens.next();
+        if let Some("newmtl") = directive {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            current = Some(Material::new(tokens.expect("material name")?));
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else {
+            continue;
+        };
+        match directive {
+            Some("Ka") => material.ambient = color(&mut tokens)?,
+            Some("Kd") => material.diffuse = color(&mut tokens)?,
+            Some("Ks") => material.specular = color(&mut tokens)?,
+            Some("Ns") => material.shininess = tokens.parse::<f64>("shininess")?,
+            Some(directive @ ("d" | "Tr")) => {
+                material.dissolve = match directive {
+                    "d" => tokens.parse::<f64>("dissolve")?,
+                    _ => 1.0 - tokens.parse::<f64>("transparency")?,
+                };
+                // MTL has no blend modes, see-through materials are alpha blended.
+                material.blend = if material.dissolve < 1.0 {
+                    Blend::Alpha
+                } else {
+                    Blend::Opaque
+                };
+            }
+            Some("map_Kd") => {
+                let (name, clamp) = map(&mut tokens)?;
+                material.diffuse_map = Some(name);
+                // Without the option the sampler keeps clamping to the edge.
+                if let Some(clamp) = clamp {
+                    let wrap = if clamp {
+                        Wrap::ClampToEdge
+                    } else {
+                        Wrap::Repeat
+                    };
+                    material.sampler.wrap_u = wrap;
+                    material.sampler.wrap_v = wrap;
+                }
+            }
+            Some("map_Bump" | "map_bump" | "bump") => {
+                material.bump_map = Some(map(&mut tokens)?.0);
+            }
+            Some(_) | None => continue,
+        }
+    }
+    if let Some(material) = current {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_mtl() {
+        let materials = load_mtl(
+            b"# comment
+newmtl crate
+Ka 0.1 0.2 0.3
+Kd 0.8
+Ks 0.5 0.5 0.5
+Ns 250
+d 0.75
+map_Kd -s 1 1 1 crate.jpg
+map_Bump crate_bump.png
+
+newmtl glass
+Tr 0.9
+
+newmtl tiles
+map_Kd -clamp off tiles.png
+",
+        )
+        .unwrap();
+
+        let crate_material = &materials["crate"];
+        assert_eq!(crate_material.ambient, (0.1, 0.2, 0.3).into());
+        assert_eq!(crate_material.diffuse, (0.8, 0.8, 0.8).into());
+        assert_eq!(crate_material.specular, (0.5, 0.5, 0.5).into());
+        assert_eq!(crate_material.shininess, 250.0);
+        assert_eq!(crate_material.dissolve, 0.75);
+        assert_eq!(crate_material.diffuse_map.as_deref(), Some("crate.jpg"));
+        assert_eq!(crate_material.bump_map.as_deref(), Some("crate_bump.png"));
+        assert_eq!(crate_material.sampler.wrap_u, Wrap::ClampToEdge);
+
+        let glass = &materials["glass"];
+        assert!((glass.dissolve - 0.1).abs() < 1e-12);
+        assert_eq!(glass.blend, Blend::Alpha);
+        assert_eq!(glass.diffuse_map, None);
+
+        let tiles = &materials["tiles"];
+        assert_eq!(tiles.sampler.wrap_u, Wrap::Repeat);
+        assert_eq!(tiles.sampler.wrap_v, Wrap::Repeat);
+    }
+
+    #[test]
+    fn test_load_mtl_errors() {
+        let error = load_mtl(b"newmtl crate\nKd 1.0 x 1.0\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 2,
+                column: 8,
+                ..
+            }
+        ));
+
+        let error = load_mtl(b"newmtl\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 1,
+                column: 7,
+                ..
+            }
+        ));
+    }
+}
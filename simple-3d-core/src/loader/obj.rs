@@ -0,0 +1,657 @@
+use std::collections::HashMap;
+
+use super::{LoadError, Tokens, lines};
+use crate::types::{
+    mesh::{Indice, MaterialRange, Mesh},
+    vector::Vector,
+};
+
+/// Face corner as written in the file: `(vertex, uv, normal)`, the uv and normal being optional.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct Polygon<'a> {
+    corners: Vec<Corner>,
+    material: &'a str,
+    /// Smoothing group, `0` when smoothing is off.
+    smoothing: u32,
+    group: usize,
+    line: usize,
+}
+
+struct Obj<'a> {
+    vertices: Vec<Vector<3>>,
+    uvs: Vec<Vector<2>>,
+    normals: Vec<Vector<3>>,
+    polygons: Vec<Polygon<'a>>,
+    groups: Vec<&'a str>,
+    libraries: Box<[Box<str>]>,
+}
+
+/// Loads all faces of the file into a single mesh.
+pub fn load_obj(data: &[u8]) -> Result<Mesh, LoadError> {
+    let file = String::from_utf8_lossy(data);
+    let obj = parse(&file)?;
+
+    let polygons = obj.polygons.iter().collect::<Vec<_>>();
+    let name = polygons.first().map_or("default", |p| obj.groups[p.group]);
+    Ok(build(&obj, name, &polygons))
+}
+
+/// Loads every object (`o`) and group (`g`) of the file as a separate named mesh.
+pub fn load_obj_groups(data: &[u8]) -> Result<Vec<Mesh>, LoadError> {
+    let file = String::from_utf8_lossy(data);
+    let obj = parse(&file)?;
+
+    let mut groups = vec![Vec::new(); obj.groups.len()];
+    for polygon in obj.polygons.iter() {
+        groups[polygon.group].push(polygon);
+    }
+    Ok(groups
+        .into_iter()
+        .enumerate()
+        .filter(|(_, polygons)| !polygons.is_empty())
+        .map(|(group, polygons)| build(&obj, obj.groups[group], &polygons))
+        .collect())
+}
+
+/// Free-form geometry directives, which the loader cannot turn into triangles.
+const FREEFORM_DIRECTIVES: [&str; 14] = [
+    "cstype", "deg", "bmat", "step", "curv", "curv2", "surf", "parm", "trim", "hole", "scrv", "sp",
+    "end", "con",
+];
+
+fn parse(file: &str) -> Result<Obj<'_>, LoadError> {
+    #[inline]
+    fn vec_n<const S: usize>(tokens: &mut Tokens, what: &str) -> Result<Vector<S>, LoadError> {
+        (0..S)
+            .map(|_| tokens.parse::<f64>(what))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Vector::from)
+    }
+    #[inline]
+    fn uv(tokens: &mut Tokens) -> Result<Vector<2>, LoadError> {
+        let u = tokens.parse::<f64>("texture coordinate")?;
+        let v = match tokens.next() {
+            Some(token) => tokens.parse_token::<f64>(token, "texture coordinate")?,
+            None => 0.0,
+        };
+        Ok((u, v).into())
+    }
+    /// Resolves a 1-based index, negative indices count back from the last element defined so far.
+    #[inline]
+    fn index(
+        tokens: &Tokens,
+        part: &str,
+        element: &'static str,
+        len: usize,
+    ) -> Result<usize, LoadError> {
+        match tokens.parse_token::<i64>(part, "index")? {
+            0 => Err(tokens.error(part, "indices start at 1")),
+            index if index > 0 => Ok(index as usize - 1),
+            index if index.unsigned_abs() as usize <= len => {
+                Ok(len - index.unsigned_abs() as usize)
+            }
+            index => Err(LoadError::IndexOutOfRange {
+                line: tokens.line(),
+                element,
+                index,
+                len,
+            }),
+        }
+    }
+    /// Parses `v`, `v/vt`, `v//vn` and `v/vt/vn` corners.
+    #[inline]
+    fn faces(tokens: &mut Tokens, lens: [usize; 3]) -> Result<Vec<Corner>, LoadError> {
+        let corners = tokens.by_ref().collect::<Vec<_>>();
+        corners
+            .into_iter()
+            .map(|token| {
+                let mut parts = token.split('/');
+                let vertex = index(tokens, parts.next().unwrap_or(token), "vertex", lens[0])?;
+                let mut optional = |element, len| match parts.next() {
+                    Some("") | None => Ok(None),
+                    Some(part) => index(tokens, part, element, len).map(Some),
+                };
+                let uv = optional("texture coordinate", lens[1])?;
+                let normal = optional("normal", lens[2])?;
+                match parts.next() {
+                    Some(_) => Err(tokens.error(token, format!("invalid face corner `{token}`"))),
+                    None => Ok((vertex, uv, normal)),
+                }
+            })
+            .collect::<Result<Vec<Corner>, _>>()
+    }
+    #[inline]
+    fn check(
+        polygon: &Polygon,
+        element: &'static str,
+        index: usize,
+        len: usize,
+    ) -> Result<(), LoadError> {
+        if index < len {
+            Ok(())
+        } else {
+            Err(LoadError::IndexOutOfRange {
+                line: polygon.line,
+                element,
+                index: index as i64 + 1,
+                len,
+            })
+        }
+    }
+
+    let mut vertices = Vec::<Vector<3>>::new();
+    let mut uvs = Vec::<Vector<2>>::new();
+    let mut normals = Vec::<Vector<3>>::new();
+    let mut polygons = Vec::<Polygon>::new();
+    let mut groups = vec!["default"];
+    let mut libraries = Vec::<Box<str>>::new();
+
+    let mut material = "none";
+    let mut smoothing = 0;
+
+    for mut tokens in lines(file) {
+        match tokens.next() {
+            Some("v") => vertices.push(vec_n::<3>(&mut tokens, "vertex coordinate")?),
+            Some("vt") => uvs.push(uv(&mut tokens)?),
+            Some("vn") => normals.push(vec_n::<3>(&mut tokens, "normal coordinate")?),
+            Some("f") => polygons.push(Polygon {
+                corners: faces(&mut tokens, [vertices.len(), uvs.len(), normals.len()])?,
+                material,
+                smoothing,
+                group: groups.len() - 1,
+                line: tokens.line(),
+            }),
+            Some("o" | "g") => {
+                let name = tokens.arguments();
+                groups.push(if name.is_empty() { "default" } else { name });
+            }
+            Some("s") => {
+                smoothing = match tokens.next() {
+                    Some("off") | None => 0,
+                    Some("on") => 1,
+                    Some(group) => tokens.parse_token::<u32>(group, "smoothing group")?,
+                };
+            }
+            Some("usemtl") => {
+                material = tokens.expect("material name")?;
+            }
+            Some("mtllib") => libraries.extend(tokens.map(Box::from)),
+            Some(directive) if FREEFORM_DIRECTIVES.contains(&directive) => {
+                return Err(LoadError::UnsupportedDirective {
+                    line: tokens.line(),
+                    directive: directive.into(),
+                });
+            }
+            Some(_) | None => continue,
+        }
+    }
+
+    for polygon in polygons.iter() {
+        for &(v, uv, n) in polygon.corners.iter() {
+            check(polygon, "vertex", v, vertices.len())?;
+            if let Some(uv) = uv {
+                check(polygon, "texture coordinate", uv, uvs.len())?;
+            }
+            if let Some(n) = n {
+                check(polygon, "normal", n, normals.len())?;
+            }
+        }
+    }
+
+    Ok(Obj {
+        vertices,
+        uvs,
+        normals,
+        polygons,
+        groups,
+        libraries: libraries.into(),
+    })
+}
+
+/// Builds a mesh from a subset of the file polygons, keeping only the vertex data they reference.
+/// Normals missing in the file are generated, flat or smoothed according to the smoothing group.
+fn build(obj: &Obj, name: &str, polygons: &[&Polygon]) -> Mesh {
+    struct Compact<T> {
+        map: HashMap<usize, usize>,
+        items: Vec<T>,
+    }
+    impl<T: Copy> Compact<T> {
+        fn new() -> Self {
+            Self {
+                map: HashMap::new(),
+                items: Vec::new(),
+            }
+        }
+        fn get(&mut self, source: &[T], index: usize) -> usize {
+            let items = &mut self.items;
+            *self.map.entry(index).or_insert_with(|| {
+                items.push(source[index]);
+                items.len() - 1
+            })
+        }
+    }
+
+    // Corners without texture coordinates share a default one, appended after the ones read from the file.
+    let default_uv = obj.uvs.len();
+    let all_uvs = obj
+        .uvs
+        .iter()
+        .copied()
+        .chain([(0.0, 0.0).into()])
+        .collect::<Vec<_>>();
+    // Generated normals are appended after the ones read from the file.
+    let mut all_normals = obj.normals.clone();
+    let mut smooth_normals = HashMap::<(usize, u32), usize>::new();
+    let faces = polygons
+        .iter()
+        .map(|polygon| {
+            let points = polygon
+                .corners
+                .iter()
+                .map(|c| obj.vertices[c.0])
+                .collect::<Vec<_>>();
+            let corners = if polygon.corners.iter().all(|c| c.2.is_some()) {
+                polygon
+                    .corners
+                    .iter()
+                    .map(|&(v, uv, n)| (v, uv, n.unwrap()))
+                    .collect::<Vec<_>>()
+            } else if polygon.smoothing == 0 {
+                all_normals.push(polygon_normal(&points));
+                let normal = all_normals.len() - 1;
+                polygon
+                    .corners
+                    .iter()
+                    .map(|&(v, uv, _)| (v, uv, normal))
+                    .collect::<Vec<_>>()
+            } else {
+                // Area weighted, so large faces dominate the shared vertex normal.
+                let weighted = newell(&points);
+                polygon
+                    .corners
+                    .iter()
+                    .map(|&(v, uv, _)| {
+                        let normal =
+                            *smooth_normals
+                                .entry((v, polygon.smoothing))
+                                .or_insert_with(|| {
+                                    all_normals.push((0.0, 0.0, 0.0).into());
+                                    all_normals.len() - 1
+                                });
+                        all_normals[normal] += weighted;
+                        (v, uv, normal)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            (points, corners, polygon.material)
+        })
+        .collect::<Vec<_>>();
+    for &normal in smooth_normals.values() {
+        all_normals[normal] = normalized(all_normals[normal]);
+    }
+
+    let mut vertices = Compact::new();
+    let mut uvs = Compact::new();
+    let mut normals = Compact::new();
+    let mut indices = Vec::<Indice>::new();
+    let mut materials = Vec::<MaterialRange>::new();
+    for (points, corners, material) in faces {
+        match materials.last() {
+            Some((_, last)) if **last == *material => {}
+            _ => materials.push((indices.len()..indices.len(), material.into())),
+        }
+
+        let corners = corners
+            .into_iter()
+            .map(|(v, uv, n)| {
+                (
+                    vertices.get(&obj.vertices, v),
+                    uvs.get(&all_uvs, uv.unwrap_or(default_uv)),
+                    normals.get(&all_normals, n),
+                )
+            })
+            .collect::<Vec<_>>();
+        indices.extend(
+            triangulate(&points)
+                .into_iter()
+                .map(|[a, b, c]| (corners[a], corners[b], corners[c])),
+        );
+        if let Some((range, _)) = materials.last_mut() {
+            range.end = indices.len();
+        }
+    }
+
+    materials.retain(|(range, _)| !range.is_empty());
+
+    let mut mesh = Mesh::new(
+        vertices.items,
+        uvs.items,
+        normals.items,
+        &indices,
+        &materials,
+    );
+    mesh.name = name.into();
+    mesh.libraries = obj.libraries.clone();
+    mesh
+}
+
+#[inline]
+fn normalized(vector: Vector<3>) -> Vector<3> {
+    if vector.magnitude() > 0.0 {
+        vector.normalize()
+    } else {
+        vector
+    }
+}
+
+/// Sum of the polygon edge cross products, its length is twice the polygon area.
+fn newell(points: &[Vector<3>]) -> Vector<3> {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .fold(Vector::from([0.0; 3]), |acc, (a, b)| {
+            acc + (
+                (a[1] - b[1]) * (a[2] + b[2]),
+                (a[2] - b[2]) * (a[0] + b[0]),
+                (a[0] - b[0]) * (a[1] + b[1]),
+            )
+                .into()
+        })
+}
+
+/// Normal of a planar polygon with counter-clockwise winding, computed with Newell's method
+/// so that it is robust for concave and slightly non-planar polygons.
+pub fn polygon_normal(points: &[Vector<3>]) -> Vector<3> {
+    normalized(newell(points))
+}
+
+/// Splits a simple polygon into triangles using ear clipping.
+/// Returns triangles as indexes into `points`, keeping the polygon winding.
+pub fn triangulate(points: &[Vector<3>]) -> Vec<[usize; 3]> {
+    #[inline]
+    fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    }
+    #[inline]
+    fn contains(p: (f64, f64), (a, b, c): ((f64, f64), (f64, f64), (f64, f64))) -> bool {
+        cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+    }
+
+    match points.len() {
+        0..=2 => return Vec::new(),
+        3 => return vec![[0, 1, 2]],
+        _ => {}
+    }
+
+    // Project onto the plane most aligned with the polygon, oriented so the polygon is counter-clockwise.
+    let normal = polygon_normal(points);
+    let axis = (0..3)
+        .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))
+        .unwrap();
+    let (u, v) = [(1, 2), (2, 0), (0, 1)][axis];
+    let flip = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+    let projected = points
+        .iter()
+        .map(|p| (p[u], p[v] * flip))
+        .collect::<Vec<_>>();
+
+    let mut remaining = (0..points.len()).collect::<Vec<_>>();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (prev, cur, next) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let triangle = (projected[prev], projected[cur], projected[next]);
+            cross(triangle.0, triangle.1, triangle.2) > f64::EPSILON
+                && remaining
+                    .iter()
+                    .filter(|&&j| j != prev && j != cur && j != next)
+                    .all(|&j| !contains(projected[j], triangle))
+        });
+
+        // Degenerate polygons have no ears left, fall back to clipping any corner.
+        let i = ear.unwrap_or(0);
+        triangles.push([
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_obj_normals() {
+        let mesh = load_obj(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1\n")
+            .unwrap();
+        assert_eq!(&*mesh.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+        assert_eq!(&*mesh.indices, &[((0, 0, 0), (1, 0, 0), (2, 0, 0))]);
+    }
+
+    #[test]
+    fn test_load_obj_computed_normals() {
+        let mesh = load_obj(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1\n").unwrap();
+        assert_eq!(&*mesh.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+        assert_eq!(&*mesh.indices, &[((0, 0, 0), (1, 0, 0), (2, 0, 0))]);
+    }
+
+    #[test]
+    fn test_load_obj_material() {
+        let mesh = load_obj(
+            b"mtllib a.mtl b.mtl\nusemtl crate\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1\n",
+        )
+        .unwrap();
+        assert_eq!(&*mesh.materials, &[(0..1, "crate".into())]);
+        assert_eq!(&*mesh.libraries, &["a.mtl".into(), "b.mtl".into()]);
+    }
+
+    #[test]
+    fn test_load_obj_material_switching() {
+        let mesh = load_obj(
+            b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0
+f 1/1 2/1 3/1
+usemtl red
+usemtl green
+f 1/1 2/1 3/1 4/1
+f 1/1 2/1 3/1
+usemtl blue
+f 1/1 3/1 4/1
+",
+        )
+        .unwrap();
+        assert_eq!(
+            &*mesh.materials,
+            &[
+                (0..1, "none".into()),
+                (1..4, "green".into()),
+                (4..5, "blue".into()),
+            ]
+        );
+        let groups = mesh
+            .material_groups()
+            .map(|(indices, material)| (indices.len(), material));
+        assert_eq!(
+            groups.collect::<Vec<_>>(),
+            [(1, "none"), (3, "green"), (1, "blue")]
+        );
+    }
+
+    #[test]
+    fn test_load_obj_groups() {
+        let meshes = load_obj_groups(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 5 5 5\nv 6 5 5\nv 5 6 5\nvt 0 0
+o Body
+g Body_front
+f 1/1 2/1 3/1
+o Wheel
+f 4/1 5/1 6/1
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            meshes.iter().map(|m| &*m.name).collect::<Vec<_>>(),
+            ["Body_front", "Wheel"]
+        );
+        let wheel = &meshes[1];
+        assert_eq!(wheel.vertices.len(), 3);
+        assert_eq!(wheel.vertices[0], (5.0, 5.0, 5.0).into());
+        assert_eq!(&*wheel.indices, &[((0, 0, 0), (1, 0, 0), (2, 0, 0))]);
+    }
+
+    #[test]
+    fn test_load_obj_smoothing_groups() {
+        let smooth = load_obj(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nvt 0 0\ns 1\nf 1/1 2/1 3/1\nf 1/1 4/1 2/1\n",
+        )
+        .unwrap();
+        let flat = load_obj(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nvt 0 0\ns off\nf 1/1 2/1 3/1\nf 1/1 4/1 2/1\n",
+        )
+        .unwrap();
+
+        assert_eq!(smooth.normals.len(), 4);
+        let (a, _, _) = smooth.indices[0];
+        let expected = Vector::from([0.0, 1.0, 1.0]).normalize();
+        assert!((smooth.normals[a.2] - expected).magnitude() < 1e-12);
+        assert_eq!(flat.normals.len(), 2);
+    }
+
+    #[test]
+    fn test_load_obj_errors() {
+        let error = load_obj(b"v 0 0 0\nv 1.0 abc 0\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 2,
+                column: 7,
+                ..
+            }
+        ));
+
+        let error = load_obj(b"v 0 0\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 1,
+                column: 6,
+                ..
+            }
+        ));
+
+        let error = load_obj(b"v 0 0 0\nvt 0 0\n\nf 1/1 2/1 3/1\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::IndexOutOfRange {
+                line: 4,
+                element: "vertex",
+                index: 2,
+                len: 1
+            }
+        ));
+
+        let error = load_obj(b"cstype bspline\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::UnsupportedDirective { line: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_load_obj_index_forms() {
+        let mesh = load_obj(
+            b"v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1
+f -3//-1 -2//-1 -1//-1
+f 1 2 3
+v 0 0 1
+vt 0.5 0.5
+f 1/1 -3/-1 4
+",
+        )
+        .unwrap();
+
+        assert_eq!(mesh.uvs.len(), 2);
+        assert_eq!(
+            &*mesh.indices,
+            &[
+                ((0, 0, 0), (1, 0, 0), (2, 0, 0)),
+                ((0, 0, 1), (1, 0, 1), (2, 0, 1)),
+                ((0, 1, 2), (1, 1, 2), (3, 0, 2)),
+            ]
+        );
+        assert_eq!(mesh.uvs[0], (0.0, 0.0).into());
+        assert_eq!(mesh.uvs[1], (0.5, 0.5).into());
+
+        let error = load_obj(b"v 0 0 0\nf -2 1 1\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::IndexOutOfRange {
+                line: 2,
+                index: -2,
+                len: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_load_obj_quad() {
+        let mesh =
+            load_obj(b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1 4/1\n").unwrap();
+        assert_eq!(mesh.indices.len(), 2);
+        assert_eq!(&*mesh.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+    }
+
+    #[test]
+    fn test_triangulate_concave() {
+        // An arrow head pointing up, with its reflex corner at index 3.
+        let points = [
+            (0.0, 0.0, 0.0),
+            (1.0, 2.0, 0.0),
+            (2.0, 0.0, 0.0),
+            (1.0, 1.0, 0.0),
+        ]
+        .map(Vector::from);
+        let triangles = triangulate(&points);
+
+        assert_eq!(triangles.len(), 2);
+        for [a, b, c] in triangles {
+            assert!(polygon_normal(&[points[a], points[b], points[c]])[2] < 0.0);
+            assert!([a, b, c].contains(&3));
+        }
+    }
+
+    #[test]
+    fn test_triangulate_keeps_area() {
+        let points = [
+            (0.0, 0.0, 0.0),
+            (0.0, 0.0, 2.0),
+            (0.0, 1.0, 2.0),
+            (0.0, 1.0, 1.0),
+            (0.0, 2.0, 1.0),
+            (0.0, 2.0, 0.0),
+        ]
+        .map(Vector::from);
+        let area = triangulate(&points)
+            .into_iter()
+            .map(|[a, b, c]| {
+                (points[b] - points[a])
+                    .cross(points[c] - points[a])
+                    .magnitude()
+                    / 2.0
+            })
+            .sum::<f64>();
+        assert!((area - 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,556 @@
+use super::{LoadError, Tokens, lines, obj::triangulate};
+use crate::types::{
+    mesh::{Indice, Mesh},
+    vector::Vector,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Maps a color channel stored with this type to `0.0..=1.0`.
+    fn color(&self, value: f64) -> f64 {
+        match self {
+            Scalar::F32 | Scalar::F64 => value,
+            Scalar::U16 | Scalar::I16 => value / u16::MAX as f64,
+            _ => value / u8::MAX as f64,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property<'a> {
+    Scalar(Scalar, &'a str),
+    List(Scalar, Scalar, &'a str),
+}
+
+#[derive(Debug)]
+struct Element<'a> {
+    name: &'a str,
+    count: usize,
+    properties: Vec<Property<'a>>,
+}
+
+/// Source of property values, either whitespace separated text or packed binary data.
+trait Values {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, LoadError>;
+    /// Error for a face referencing a vertex past `len`, located at the last read value.
+    fn out_of_range(&self, index: i64, len: usize) -> LoadError;
+}
+
+struct AsciiValues<'a, L: Iterator<Item = Tokens<'a>>> {
+    lines: L,
+    current: Option<Tokens<'a>>,
+}
+
+impl<'a, L: Iterator<Item = Tokens<'a>>> Values for AsciiValues<'a, L> {
+    fn read(&mut self, _: Scalar) -> Result<f64, LoadError> {
+        loop {
+            if let Some(tokens) = self.current.as_mut()
+                && let Some(token) = tokens.next()
+            {
+                return tokens.parse_token::<f64>(token, "value");
+            }
+            match self.lines.next() {
+                Some(tokens) => self.current = Some(tokens),
+                None => {
+                    return Err(LoadError::Parse {
+                        line: self.line(),
+                        column: 1,
+                        message: "unexpected end of file".into(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn out_of_range(&self, index: i64, len: usize) -> LoadError {
+        LoadError::IndexOutOfRange {
+            line: self.line(),
+            element: "vertex",
+            index,
+            len,
+        }
+    }
+}
+
+impl<'a, L: Iterator<Item = Tokens<'a>>> AsciiValues<'a, L> {
+    fn line(&self) -> usize {
+        self.current.as_ref().map_or(0, |tokens| tokens.line())
+    }
+}
+
+struct BinaryValues<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl Values for BinaryValues<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, LoadError> {
+        let size = scalar.size();
+        let Some(bytes) = self.data.get(self.offset..self.offset + size) else {
+            return Err(LoadError::InvalidBinary {
+                offset: self.data.len(),
+                message: "unexpected end of file".into(),
+            });
+        };
+        self.offset += size;
+
+        let mut buffer = [0; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.big_endian {
+            buffer[..size].reverse();
+        }
+        Ok(match scalar {
+            Scalar::I8 => i8::from_le_bytes([buffer[0]]) as f64,
+            Scalar::U8 => buffer[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            Scalar::U32 => u32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            Scalar::F32 => f32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+            Scalar::F64 => f64::from_le_bytes(buffer),
+        })
+    }
+
+    fn out_of_range(&self, index: i64, len: usize) -> LoadError {
+        LoadError::InvalidBinary {
+            offset: self.offset,
+            message: format!("vertex index {index} is out of range, {len} defined").into(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<Vector<3>>,
+    normals: Vec<Vector<3>>,
+    uvs: Vec<Vector<2>>,
+    colors: Vec<Vector<4>>,
+}
+
+/// Loads an ASCII or binary (little or big endian) PLY file, including per vertex colors.
+pub fn load_ply(data: &[u8]) -> Result<Mesh, LoadError> {
+    let Some(header_end) = data
+        .windows(b"end_header".len())
+        .position(|window| window == b"end_header")
+    else {
+        return Err(LoadError::Parse {
+            line: 1,
+            column: 1,
+            message: "missing `end_header`".into(),
+        });
+    };
+    let body_start = data[header_end..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(data.len(), |newline| header_end + newline + 1);
+
+    let header = String::from_utf8_lossy(&data[..header_end]);
+    let (format, elements) = parse_header(&header)?;
+
+    match format {
+        Format::Ascii => {
+            // Parse the whole file as text, so errors report lines counted from the start of the file.
+            let file = String::from_utf8_lossy(data);
+            let mut values = AsciiValues {
+                lines: lines(&file).skip(header.lines().count() + 1),
+                current: None,
+            };
+            read_body(&elements, &mut values)
+        }
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+            let mut values = BinaryValues {
+                data,
+                offset: body_start,
+                big_endian: format == Format::BinaryBigEndian,
+            };
+            read_body(&elements, &mut values)
+        }
+    }
+}
+
+fn parse_header(header: &str) -> Result<(Format, Vec<Element<'_>>), LoadError> {
+    #[inline]
+    fn scalar(tokens: &Tokens, token: &str) -> Result<Scalar, LoadError> {
+        Scalar::parse(token).ok_or_else(|| tokens.error(token, format!("unknown type `{token}`")))
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<Element>::new();
+
+    for mut tokens in lines(header) {
+        match tokens.next() {
+            Some("ply") | Some("comment") | Some("obj_info") | None => continue,
+            Some("format") => {
+                let token = tokens.expect("format")?;
+                format = Some(match token {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(tokens.error(token, format!("unknown format `{token}`"))),
+                });
+            }
+            Some("element") => {
+                let name = tokens.expect("element name")?;
+                let count = tokens.parse::<usize>("element count")?;
+                elements.push(Element {
+                    name,
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let property = match tokens.expect("property type")? {
+                    "list" => {
+                        let count = tokens.expect("list count type")?;
+                        let count = scalar(&tokens, count)?;
+                        let item = tokens.expect("list item type")?;
+                        let item = scalar(&tokens, item)?;
+                        Property::List(count, item, tokens.expect("property name")?)
+                    }
+                    token => {
+                        let scalar = scalar(&tokens, token)?;
+                        Property::Scalar(scalar, tokens.expect("property name")?)
+                    }
+                };
+                let Some(element) = elements.last_mut() else {
+                    return Err(LoadError::Parse {
+                        line: tokens.line(),
+                        column: 1,
+                        message: "property outside of an element".into(),
+                    });
+                };
+                element.properties.push(property);
+            }
+            Some(directive) => {
+                return Err(LoadError::UnsupportedDirective {
+                    line: tokens.line(),
+                    directive: directive.into(),
+                });
+            }
+        }
+    }
+
+    let format = format.ok_or_else(|| LoadError::Parse {
+        line: 1,
+        column: 1,
+        message: "missing `format`".into(),
+    })?;
+    Ok((format, elements))
+}
+
+fn read_body(elements: &[Element], values: &mut impl Values) -> Result<Mesh, LoadError> {
+    let mut vertices = Vertices::default();
+    let mut faces = Vec::<Vec<usize>>::new();
+    let vertex_count = elements
+        .iter()
+        .find(|element| element.name == "vertex")
+        .map_or(0, |element| element.count);
+
+    for element in elements {
+        for _ in 0..element.count {
+            let mut position = [0.0; 3];
+            let mut normal = [0.0; 3];
+            let mut uv = [0.0; 2];
+            let mut color = [1.0; 4];
+            let (mut has_normal, mut has_uv, mut has_color) = (false, false, false);
+
+            for property in element.properties.iter() {
+                match *property {
+                    Property::Scalar(scalar, name) => {
+                        let value = values.read(scalar)?;
+                        match name {
+                            "x" => position[0] = value,
+                            "y" => position[1] = value,
+                            "z" => position[2] = value,
+                            "nx" => (normal[0], has_normal) = (value, true),
+                            "ny" => (normal[1], has_normal) = (value, true),
+                            "nz" => (normal[2], has_normal) = (value, true),
+                            "u" | "s" | "texture_u" | "texture_s" => {
+                                (uv[0], has_uv) = (value, true);
+                            }
+                            "v" | "t" | "texture_v" | "texture_t" => {
+                                (uv[1], has_uv) = (value, true);
+                            }
+                            "red" | "diffuse_red" => {
+                                (color[0], has_color) = (scalar.color(value), true);
+                            }
+                            "green" | "diffuse_green" => {
+                                (color[1], has_color) = (scalar.color(value), true);
+                            }
+                            "blue" | "diffuse_blue" => {
+                                (color[2], has_color) = (scalar.color(value), true);
+                            }
+                            "alpha" => (color[3], has_color) = (scalar.color(value), true),
+                            _ => {}
+                        }
+                    }
+                    Property::List(count, item, name) => {
+                        let count = values.read(count)? as usize;
+                        let items = (0..count)
+                            .map(|_| values.read(item))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        if element.name == "face"
+                            && (name == "vertex_indices" || name == "vertex_index")
+                        {
+                            // Negative and fractional indices are as out of range as ones past the end.
+                            if let Some(&index) = items
+                                .iter()
+                                .find(|&&i| i < 0.0 || i.fract() != 0.0 || i >= vertex_count as f64)
+                            {
+                                return Err(values.out_of_range(index as i64, vertex_count));
+                            }
+                            faces.push(items.into_iter().map(|i| i as usize).collect());
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                vertices.positions.push(position.into());
+                if has_normal {
+                    vertices.normals.push(normal.into());
+                }
+                if has_uv {
+                    vertices.uvs.push(uv.into());
+                }
+                if has_color {
+                    vertices.colors.push(color.into());
+                }
+            }
+        }
+    }
+
+    Ok(build(vertices, &faces))
+}
+
+fn build(vertices: Vertices, faces: &[Vec<usize>]) -> Mesh {
+    let Vertices {
+        positions,
+        mut normals,
+        mut uvs,
+        colors,
+    } = vertices;
+    let count = positions.len();
+
+    let polygons = faces
+        .iter()
+        .map(|face| {
+            let points = face.iter().map(|&i| positions[i]).collect::<Vec<_>>();
+            (face, triangulate(&points), points)
+        })
+        .collect::<Vec<_>>();
+
+    // Scans usually come without normals, smooth them over the faces sharing each vertex.
+    if normals.len() != count {
+        normals = vec![(0.0, 0.0, 0.0).into(); count];
+        for (face, triangles, points) in polygons.iter() {
+            for &[a, b, c] in triangles.iter() {
+                let weighted = (points[b] - points[a]).cross(points[c] - points[a]);
+                for corner in [a, b, c] {
+                    normals[face[corner]] += weighted;
+                }
+            }
+        }
+        for normal in normals.iter_mut() {
+            if normal.magnitude() > 0.0 {
+                *normal = normal.normalize();
+            }
+        }
+    }
+    let uv_index = |vertex: usize| if uvs.len() == count { vertex } else { 0 };
+
+    let indices = polygons
+        .iter()
+        .flat_map(|(face, triangles, _)| {
+            triangles.iter().map(|&[a, b, c]| {
+                let corner = |i: usize| (face[i], uv_index(face[i]), face[i]);
+                (corner(a), corner(b), corner(c))
+            })
+        })
+        .collect::<Vec<Indice>>();
+    if uvs.len() != count {
+        uvs = vec![(0.0, 0.0).into()];
+    }
+
+    let mut mesh = Mesh::new(
+        positions,
+        uvs,
+        normals,
+        &indices,
+        &[(0..indices.len(), "none".into())],
+    );
+    if colors.len() == count {
+        mesh.colors = colors.into();
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_ply_ascii() {
+        let mesh = load_ply(
+            b"ply
+format ascii 1.0
+comment made by hand
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+",
+        )
+        .unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 2);
+        assert_eq!(mesh.colors[1], (0.0, 1.0, 0.0, 1.0).into());
+        assert_eq!(mesh.normals[0], (0.0, 0.0, 1.0).into());
+    }
+
+    #[test]
+    fn test_load_ply_binary() {
+        fn load(big_endian: bool) -> Mesh {
+            let format = if big_endian {
+                "binary_big_endian"
+            } else {
+                "binary_little_endian"
+            };
+            let mut data = format!(
+                "ply\nformat {format} 1.0\nelement vertex 3\nproperty double x\nproperty double y\nproperty double z\nproperty float nx\nproperty float ny\nproperty float nz\nelement face 1\nproperty list uchar uint vertex_index\nend_header\n"
+            )
+            .into_bytes();
+            let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+            for vertex in vertices {
+                for value in vertex {
+                    data.extend(if big_endian {
+                        f64::to_be_bytes(value)
+                    } else {
+                        f64::to_le_bytes(value)
+                    });
+                }
+                for value in [0.0f32, 0.0, -1.0] {
+                    data.extend(if big_endian {
+                        value.to_be_bytes()
+                    } else {
+                        value.to_le_bytes()
+                    });
+                }
+            }
+            data.push(3);
+            for index in [0u32, 1, 2] {
+                data.extend(if big_endian {
+                    index.to_be_bytes()
+                } else {
+                    index.to_le_bytes()
+                });
+            }
+            load_ply(&data).unwrap()
+        }
+
+        for mesh in [load(false), load(true)] {
+            assert_eq!(mesh.vertices[1], (1.0, 0.0, 0.0).into());
+            assert_eq!(mesh.normals[2], (0.0, 0.0, -1.0).into());
+            assert_eq!(&*mesh.indices, &[((0, 0, 0), (1, 0, 1), (2, 0, 2))]);
+            assert!(mesh.colors.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_load_ply_errors() {
+        let error = load_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0\n3 0 0 1\n",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::IndexOutOfRange {
+                line: 9,
+                index: 1,
+                len: 1,
+                ..
+            }
+        ));
+
+        for index in ["-1", "0.5"] {
+            let data = format!(
+                "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0\n1\n2\n3 1 2 {index}\n"
+            );
+            assert!(matches!(
+                load_ply(data.as_bytes()),
+                Err(LoadError::IndexOutOfRange {
+                    line: 11,
+                    len: 3,
+                    ..
+                })
+            ));
+        }
+
+        let error = load_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\nabc\n",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 6,
+                column: 1,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+use serde::{Deserialize, Deserializer, de::Error};
+
+use super::LoadError;
+use crate::{
+    Action, Bindings, Config, FAR, FOV, NEAR,
+    types::{
+        camera::{Camera, CameraProperties},
+        keys::Key,
+        light::{Light, Shading},
+        material::{Cull, FaceCulling, RenderMode, Winding},
+        mesh::Mesh,
+        screen::Antialiasing,
+        vector::Vector,
+    },
+};
+
+/// Scene description: the objects to load, the camera and the actions bound to timers and keys.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scene {
+    #[serde(default)]
+    pub objects: Vec<SceneObject>,
+    /// Camera of the scene, the one of a loaded glTF file or the default one when not set.
+    pub camera: Option<SceneCamera>,
+    #[serde(default)]
+    timers: Vec<Timer>,
+    #[serde(default)]
+    keys: Vec<KeyBinding>,
+    #[serde(default)]
+    lights: Vec<SceneLight>,
+    #[serde(default)]
+    antialiasing: Antialiasing,
+    #[serde(default)]
+    shading: Shading,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneObject {
+    /// Model file, in any format the loaders support.
+    pub model: Box<str>,
+    /// Texture painted on every face, instead of the materials of the model.
+    pub texture: Option<Box<str>>,
+    #[serde(default = "one")]
+    pub scale: [f64; 3],
+    /// Rotation around the x, y and z axes in radians.
+    #[serde(default)]
+    pub rotation: [f64; 3],
+    #[serde(default)]
+    pub translation: [f64; 3],
+    /// Runs `Mesh::repair` on the model before using it.
+    #[serde(default)]
+    pub repair: bool,
+    /// Number of simplified levels drawn when the model is small on screen.
+    #[serde(default)]
+    pub lods: usize,
+    /// Faces skipped when drawing, instead of the culling of the model materials.
+    pub culling: Option<Cull>,
+    /// Winding of the front faces, counter-clockwise unless set.
+    pub front_face: Option<Winding>,
+    /// What is drawn of the model, instead of the mode of the app.
+    pub render_mode: Option<RenderMode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneCamera {
+    pub position: [f64; 3],
+    pub target: [f64; 3],
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    pub near: f64,
+    pub far: f64,
+}
+
+/// Light of the scene, the fields its kind does not use are ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SceneLight {
+    pub kind: LightKind,
+    pub color: [f64; 3],
+    /// Scales the colour.
+    pub intensity: f64,
+    pub position: [f64; 3],
+    pub direction: [f64; 3],
+    /// Distance at which point and spot lights fade out to nothing.
+    pub range: f64,
+    /// Angles off the axis of a spot light where it starts to fade and where it is gone, in degrees.
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LightKind {
+    Ambient,
+    Directional,
+    #[default]
+    Point,
+    Spot,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Timer {
+    /// Milliseconds between two actions.
+    #[serde(deserialize_with = "interval")]
+    interval: u64,
+    action: SceneAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyBinding {
+    #[serde(deserialize_with = "key")]
+    key: Key,
+    action: SceneAction,
+}
+
+/// `Action`s a scene can bind, objects are indexed by the meshes in the order they are loaded.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum SceneAction {
+    MoveCamera([f64; 3]),
+    SetCamera(SceneCamera),
+    RotateObject {
+        object: usize,
+        delta: [f64; 3],
+    },
+    TranslateObject {
+        object: usize,
+        delta: [f64; 3],
+    },
+    ScaleObject {
+        object: usize,
+        factor: [f64; 3],
+    },
+    SetRenderMode(RenderMode),
+    CycleRenderMode,
+    SetObjectRenderMode {
+        object: usize,
+        mode: Option<RenderMode>,
+    },
+    AddLight(SceneLight),
+    MoveLight {
+        light: usize,
+        delta: [f64; 3],
+    },
+    SetShading(Shading),
+    CycleShading,
+    End,
+}
+
+impl From<&SceneAction> for Action {
+    fn from(action: &SceneAction) -> Self {
+        match *action {
+            SceneAction::MoveCamera(delta) => Action::MoveCamera(delta.into()),
+            SceneAction::SetCamera(ref camera) => Action::SetCamera(Box::new(camera.camera())),
+            SceneAction::RotateObject { object, delta } => {
+                Action::RotateObject(object, delta.into())
+            }
+            SceneAction::TranslateObject { object, delta } => {
+                Action::TranslateObject(object, delta.into())
+            }
+            SceneAction::ScaleObject { object, factor } => {
+                Action::ScaleObject(object, factor.into())
+            }
+            SceneAction::SetRenderMode(mode) => Action::SetRenderMode(mode),
+            SceneAction::CycleRenderMode => Action::CycleRenderMode,
+            SceneAction::SetObjectRenderMode { object, mode } => {
+                Action::SetObjectRenderMode(object, mode)
+            }
+            SceneAction::SetShading(shading) => Action::SetShading(shading),
+            SceneAction::CycleShading => Action::CycleShading,
+            SceneAction::AddLight(ref light) => Action::AddLight(light.light()),
+            SceneAction::MoveLight { light, delta } => Action::MoveLight(light, delta.into()),
+            SceneAction::End => Action::End,
+        }
+    }
+}
+
+impl Scene {
+    pub fn config(&self) -> Config {
+        Config {
+            antialiasing: self.antialiasing,
+            shading: self.shading,
+        }
+    }
+
+    /// Lights of the scene, to add with `Action::AddLight`.
+    pub fn lights(&self) -> Vec<Light> {
+        self.lights.iter().map(SceneLight::light).collect()
+    }
+
+    pub fn bindings(&self) -> Bindings {
+        Bindings {
+            timers: self
+                .timers
+                .iter()
+                .map(|timer| (timer.interval, (&timer.action).into()))
+                .collect(),
+            keys: self
+                .keys
+                .iter()
+                .map(|binding| (binding.key, (&binding.action).into()))
+                .collect(),
+        }
+    }
+}
+
+impl SceneObject {
+    /// Applies the repair, levels of detail, drawing options, initial transformation and texture to
+    /// a mesh loaded from `model`.
+    pub fn apply(&self, mesh: &mut Mesh) {
+        if self.repair {
+            mesh.repair(&Default::default());
+        }
+        if self.lods > 0 {
+            mesh.generate_lods(self.lods);
+        }
+        if self.culling.is_some() || self.front_face.is_some() {
+            mesh.culling = Some(FaceCulling {
+                cull: self.culling.unwrap_or_default(),
+                front_face: self.front_face.unwrap_or_default(),
+            });
+        }
+        if self.render_mode.is_some() {
+            mesh.render_mode = self.render_mode;
+        }
+        mesh.scale(self.scale);
+        mesh.rotate(self.rotation);
+        mesh.translate(self.translation);
+        if let Some(texture) = &self.texture {
+            for (_, material) in mesh.materials.iter_mut() {
+                *material = texture.clone();
+            }
+        }
+    }
+}
+
+impl SceneCamera {
+    pub fn camera(&self) -> Camera {
+        // The aspect ratio is replaced by the one of the screen the camera is used with.
+        let properties = CameraProperties::new(self.fov.to_radians(), 1.0, self.near, self.far);
+        Camera::looking_at(properties, self.position, self.target, (0.0, 1.0, 0.0))
+    }
+}
+
+impl SceneLight {
+    pub fn light(&self) -> Light {
+        let color = Vector::from(self.color) * self.intensity;
+        let (position, direction) = (self.position.into(), self.direction.into());
+        match self.kind {
+            LightKind::Ambient => Light::Ambient { color },
+            LightKind::Directional => Light::Directional { direction, color },
+            LightKind::Point => Light::Point {
+                position,
+                color,
+                range: self.range,
+            },
+            LightKind::Spot => Light::Spot {
+                position,
+                direction,
+                color,
+                range: self.range,
+                inner: self.inner_angle.to_radians(),
+                outer: self.outer_angle.to_radians(),
+            },
+        }
+    }
+}
+
+impl Default for SceneLight {
+    fn default() -> Self {
+        Self {
+            kind: LightKind::default(),
+            color: [1.0; 3],
+            intensity: 1.0,
+            position: [0.0; 3],
+            direction: [0.0, 0.0, -1.0],
+            range: 10.0,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
+        }
+    }
+}
+
+impl Default for SceneCamera {
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0, 5.0],
+            target: [0.0, 0.0, 0.0],
+            fov: FOV.to_degrees(),
+            near: NEAR,
+            far: FAR,
+        }
+    }
+}
+
+fn one() -> [f64; 3] {
+    [1.0; 3]
+}
+
+fn key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Key, D::Error> {
+    let name = <Box<str>>::deserialize(deserializer)?;
+    Key::from_name(&name).ok_or_else(|| D::Error::custom(format!("unknown key `{name}`")))
+}
+
+fn interval<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Err(D::Error::custom(
+            "the interval must be at least 1 millisecond",
+        )),
+        interval => Ok(interval),
+    }
+}
+
+/// Loads a TOML scene description.
+pub fn load_scene(data: &[u8]) -> Result<Scene, LoadError> {
+    let file = String::from_utf8_lossy(data);
+    toml::from_str(&file).map_err(|error| {
+        let offset = error.span().map_or(0, |span| span.start);
+        let before = file.get(..offset).unwrap_or(&file);
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        LoadError::Parse {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: error.message().into(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_scene() {
+        let scene = load_scene(
+            br#"
+antialiasing = { msaa = 4 }
+shading = "gouraud"
+
+[camera]
+position = [0.0, 1.0, 10.0]
+fov = 60
+
+[[objects]]
+model = "cube.obj"
+texture = "crate.jpg"
+scale = [2.0, 2.0, 2.0]
+translation = [1.0, 0.0, 0.0]
+
+[[objects]]
+model = "teapot.glb"
+culling = "none"
+render_mode = "hidden_line"
+
+[[lights]]
+kind = "ambient"
+intensity = 0.2
+
+[[lights]]
+kind = "spot"
+position = [0.0, 5.0, 0.0]
+direction = [0.0, -1.0, 0.0]
+outer_angle = 45
+
+[[timers]]
+interval = 10
+action = { rotate_object = { object = 0, delta = [0.01, 0.02, 0.03] } }
+
+[[keys]]
+key = "ArrowUp"
+action = { move_camera = [0.0, 0.1, 0.0] }
+
+[[keys]]
+key = "r"
+action = "cycle_render_mode"
+
+[[keys]]
+key = "c"
+action = { set_camera = { position = [0.0, 0.0, 5.0] } }
+
+[[keys]]
+key = "q"
+action = "end"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.objects[1].scale, [1.0; 3]);
+        assert_eq!(scene.objects[1].culling, Some(Cull::None));
+        assert_eq!(scene.objects[1].render_mode, Some(RenderMode::HiddenLine));
+        let camera = scene.camera.as_ref().unwrap();
+        assert_eq!(camera.target, [0.0; 3]);
+        assert_eq!(camera.far, FAR);
+        assert!((camera.camera().properties().fov() - 60f64.to_radians()).abs() < 1e-12);
+
+        assert_eq!(scene.config().antialiasing, Antialiasing::Msaa(4));
+        assert_eq!(scene.config().shading, Shading::Gouraud);
+        let lights = scene.lights();
+        assert_eq!(
+            lights[0],
+            Light::Ambient {
+                color: (0.2, 0.2, 0.2).into()
+            }
+        );
+        assert!(matches!(
+            lights[1],
+            Light::Spot { range: 10.0, outer, .. } if (outer - 45f64.to_radians()).abs() < 1e-12
+        ));
+
+        let bindings = scene.bindings();
+        assert!(matches!(
+            bindings.timers[..],
+            [(10, Action::RotateObject(0, _))]
+        ));
+        assert!(matches!(
+            bindings.keys[..],
+            [
+                (Key::ArrowUp, Action::MoveCamera(_)),
+                (Key::Char('r'), Action::CycleRenderMode),
+                (Key::Char('c'), Action::SetCamera(_)),
+                (Key::Char('q'), Action::End)
+            ]
+        ));
+
+        let mut mesh = Mesh::new([], [], [], &[], &[(0..0, "wood".into())]);
+        scene.objects[0].apply(&mut mesh);
+        assert_eq!(&*mesh.materials[0].1, "crate.jpg");
+        assert_eq!(
+            mesh.transformation_matrix(),
+            &crate::transformations::transformation(
+                (2.0, 2.0, 2.0),
+                (0.0, 0.0, 0.0),
+                (1.0, 0.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn test_load_scene_errors() {
+        let error = load_scene(b"[[keys]]\nkey = \"Escape\"\naction = \"end\"\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 2,
+                column: 7,
+                ..
+            }
+        ));
+
+        let error = load_scene(b"[[objects]]\nmodel = \"cube.obj\"\nsize = 2\n").unwrap_err();
+        assert!(matches!(error, LoadError::Parse { line: 3, .. }));
+
+        let error = load_scene(b"[[timers]]\ninterval = 0\naction = \"end\"\n").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse {
+                line: 2,
+                column: 12,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use super::{LoadError, lines, obj::polygon_normal, obj::triangulate};
+use crate::types::{
+    mesh::{Indice, Mesh},
+    vector::Vector,
+};
+
+const HEADER_SIZE: usize = 80;
+const FACET_SIZE: usize = 50;
+
+struct Facet {
+    normal: Vector<3>,
+    vertices: Vec<Vector<3>>,
+}
+
+/// Loads an ASCII or binary STL file, welding identical vertices into an indexed mesh.
+pub fn load_stl(data: &[u8]) -> Result<Mesh, LoadError> {
+    if is_binary(data) {
+        load_binary(data)
+    } else {
+        load_ascii(data)
+    }
+}
+
+/// Binary files may also start with `solid`, so the size matching the facet count takes precedence,
+/// and text files never contain NUL bytes.
+fn is_binary(data: &[u8]) -> bool {
+    if let Some(count) = data.get(HEADER_SIZE..HEADER_SIZE + 4) {
+        let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+        if binary_size(count) == Some(data.len()) {
+            return true;
+        }
+    }
+    !data.trim_ascii_start().starts_with(b"solid") || data.contains(&0)
+}
+
+/// Bytes in a binary file with `count` facets, `None` if it does not fit in a `usize`.
+fn binary_size(count: usize) -> Option<usize> {
+    count.checked_mul(FACET_SIZE)?.checked_add(HEADER_SIZE + 4)
+}
+
+fn load_binary(data: &[u8]) -> Result<Mesh, LoadError> {
+    #[inline]
+    fn vector(data: &[u8], offset: usize) -> Vector<3> {
+        let f = |i: usize| {
+            let start = offset + i * 4;
+            f32::from_le_bytes(data[start..start + 4].try_into().unwrap()) as f64
+        };
+        (f(0), f(1), f(2)).into()
+    }
+
+    let Some(count) = data.get(HEADER_SIZE..HEADER_SIZE + 4) else {
+        return Err(LoadError::InvalidBinary {
+            offset: data.len(),
+            message: "missing header".into(),
+        });
+    };
+    let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+    let Some(expected) = binary_size(count).filter(|&expected| expected <= data.len()) else {
+        return Err(LoadError::InvalidBinary {
+            offset: data.len(),
+            message: format!("expected {count} facets, the file is too short").into(),
+        });
+    };
+
+    let facets = data[HEADER_SIZE + 4..expected]
+        .chunks_exact(FACET_SIZE)
+        .map(|facet| Facet {
+            normal: vector(facet, 0),
+            vertices: (1..=3).map(|i| vector(facet, i * 12)).collect(),
+        })
+        .collect::<Vec<_>>();
+    Ok(build("default", &facets))
+}
+
+fn load_ascii(data: &[u8]) -> Result<Mesh, LoadError> {
+    let file = String::from_utf8_lossy(data);
+
+    let mut name = "default";
+    let mut facets = Vec::<Facet>::new();
+    let mut current = None::<Facet>;
+
+    for mut tokens in lines(&file) {
+        match tokens.next() {
+            Some("solid") => {
+                let arguments = tokens.arguments();
+                if !arguments.is_empty() {
+                    name = arguments;
+                }
+            }
+            Some("facet") => {
+                let normal = match tokens.next() {
+                    Some("normal") => (0..3)
+                        .map(|_| tokens.parse::<f64>("normal coordinate"))
+                        .collect::<Result<Vector<3>, _>>()?,
+                    _ => (0.0, 0.0, 0.0).into(),
+                };
+                current = Some(Facet {
+                    normal,
+                    vertices: Vec::with_capacity(3),
+                });
+            }
+            Some("vertex") => {
+                let vertex = (0..3)
+                    .map(|_| tokens.parse::<f64>("vertex coordinate"))
+                    .collect::<Result<Vector<3>, _>>()?;
+                let Some(facet) = current.as_mut() else {
+                    return Err(LoadError::Parse {
+                        line: tokens.line(),
+                        column: 1,
+                        message: "vertex outside of a facet".into(),
+                    });
+                };
+                facet.vertices.push(vertex);
+            }
+            Some("endfacet") => facets.extend(current.take()),
+            Some(_) | None => continue,
+        }
+    }
+
+    Ok(build(name, &facets))
+}
+
+fn build(name: &str, facets: &[Facet]) -> Mesh {
+    #[inline]
+    fn key(vector: Vector<3>) -> [u64; 3] {
+        // Adding zero turns -0.0 into 0.0, so both weld together.
+        vector.map(|v| (v + 0.0).to_bits())
+    }
+    #[inline]
+    fn weld(
+        map: &mut HashMap<[u64; 3], usize>,
+        items: &mut Vec<Vector<3>>,
+        vector: Vector<3>,
+    ) -> usize {
+        *map.entry(key(vector)).or_insert_with(|| {
+            items.push(vector);
+            items.len() - 1
+        })
+    }
+
+    let mut vertices = Vec::new();
+    let mut vertex_map = HashMap::new();
+    let mut normals = Vec::new();
+    let mut normal_map = HashMap::new();
+    let mut indices = Vec::<Indice>::new();
+
+    for facet in facets {
+        // Many exporters leave the facet normal zeroed, recompute it from the winding then.
+        let normal = if facet.normal.magnitude() > 0.0 {
+            facet.normal.normalize()
+        } else {
+            polygon_normal(&facet.vertices)
+        };
+        let normal = weld(&mut normal_map, &mut normals, normal);
+        let corners = facet
+            .vertices
+            .iter()
+            .map(|&vertex| (weld(&mut vertex_map, &mut vertices, vertex), 0, normal))
+            .collect::<Vec<_>>();
+        indices.extend(
+            triangulate(&facet.vertices)
+                .into_iter()
+                .map(|[a, b, c]| (corners[a], corners[b], corners[c])),
+        );
+    }
+
+    let mut mesh = Mesh::new(
+        vertices,
+        [(0.0, 0.0).into()],
+        normals,
+        &indices,
+        &[(0..indices.len(), "none".into())],
+    );
+    mesh.name = name.into();
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &[u8] = b"solid part
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 0 1 0
+  endloop
+endfacet
+facet normal 0 0 0
+  outer loop
+    vertex 1 0 0
+    vertex 1 1 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid part
+";
+
+    fn binary(facets: &[[[f32; 3]; 4]]) -> Vec<u8> {
+        // Header deliberately starts with `solid`, like many exporters write it.
+        let mut data = b"solid binary".to_vec();
+        data.resize(HEADER_SIZE, 0);
+        data.extend((facets.len() as u32).to_le_bytes());
+        for facet in facets {
+            for value in facet.iter().flatten() {
+                data.extend(value.to_le_bytes());
+            }
+            data.extend([0, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_load_stl_ascii() {
+        let mesh = load_stl(ASCII).unwrap();
+
+        assert_eq!(&*mesh.name, "part");
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 2);
+        assert_eq!(&*mesh.normals, &[Vector::from([0.0, 0.0, 1.0])]);
+        assert_eq!(mesh.indices[1].2, (2, 0, 0));
+    }
+
+    #[test]
+    fn test_load_stl_binary() {
+        let data = binary(&[
+            [
+                [0.0, 0.0, 1.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            [
+                [0.0, 0.0, -1.0],
+                [0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 0.0, 0.0],
+            ],
+        ]);
+        let mesh = load_stl(&data).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.normals.len(), 2);
+        assert_eq!(
+            &*mesh.indices,
+            &[
+                ((0, 0, 0), (1, 0, 0), (2, 0, 0)),
+                ((0, 0, 1), (2, 0, 1), (1, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_stl_truncated() {
+        let mut data = binary(&[[[0.0; 3]; 4]]);
+        data[HEADER_SIZE] = 2;
+        assert!(matches!(
+            load_stl(&data),
+            Err(LoadError::InvalidBinary { offset: 134, .. })
+        ));
+
+        // Counts far past the end of the file, whose size may not even fit in a `usize`.
+        data[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            load_stl(&data),
+            Err(LoadError::InvalidBinary { offset: 134, .. })
+        ));
+    }
+}
@@ -0,0 +1,421 @@
+//! Procedural meshes centered at the origin with `+y` up, faces wound counter-clockwise seen from outside.
+
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::types::{
+    mesh::{Indice, Mesh},
+    validation::is_degenerate,
+    vector::Vector,
+};
+
+/// Point of a profile revolved around the y axis: `(radius, y, normal radius, normal y)`.
+type ProfilePoint = (f64, f64, f64, f64);
+
+#[derive(Default)]
+struct Builder {
+    vertices: Vec<Vector<3>>,
+    uvs: Vec<Vector<2>>,
+    normals: Vec<Vector<3>>,
+    indices: Vec<Indice>,
+}
+
+impl Builder {
+    /// Adds a vertex, its uv and normal all stored at the returned index.
+    fn vertex(&mut self, position: Vector<3>, uv: Vector<2>, normal: Vector<3>) -> usize {
+        self.vertices.push(position);
+        self.uvs.push(uv);
+        self.normals.push(normal);
+        self.vertices.len() - 1
+    }
+
+    /// Adds a triangle of `(vertex, uv)` corners, the vertex index also picking the normal.
+    /// Triangles collapsed into a line, like the ones touching the poles of a sphere, are skipped.
+    fn triangle(&mut self, corners: [(usize, usize); 3]) {
+        let [a, b, c] = corners.map(|(vertex, _)| self.vertices[vertex]);
+        if is_degenerate(a, b, c) {
+            return;
+        }
+        let [a, b, c] = corners.map(|(vertex, uv)| (vertex, uv, vertex));
+        self.indices.push((a, b, c));
+    }
+
+    /// Adds the quad `a b c d` of vertices with their own uvs as two triangles.
+    fn quad(&mut self, a: usize, b: usize, c: usize, d: usize) {
+        self.triangle([(a, a), (b, b), (c, c)]);
+        self.triangle([(a, a), (c, c), (d, d)]);
+    }
+
+    /// Revolves the profile, listed from top to bottom, around the y axis.
+    fn lathe(&mut self, profile: &[ProfilePoint], segments: usize) {
+        let start = self.vertices.len();
+        let rows = profile.len();
+        for (i, &(radius, y, normal_radius, normal_y)) in profile.iter().enumerate() {
+            // The first column is repeated at the end, so the texture seam gets its own uvs.
+            for j in 0..=segments {
+                let direction = direction(j as f64 / segments as f64);
+                let position = direction * radius + Vector::from([0.0, y, 0.0]);
+                let normal =
+                    (direction * normal_radius + Vector::from([0.0, normal_y, 0.0])).normalize();
+                let uv = [j as f64 / segments as f64, i as f64 / (rows - 1) as f64];
+                self.vertex(position, uv.into(), normal);
+            }
+        }
+
+        let index = |i: usize, j: usize| start + i * (segments + 1) + j;
+        for i in 0..rows - 1 {
+            for j in 0..segments {
+                self.quad(
+                    index(i, j),
+                    index(i + 1, j),
+                    index(i + 1, j + 1),
+                    index(i, j + 1),
+                );
+            }
+        }
+    }
+
+    /// Adds a flat disc at height `y`, facing up or down.
+    fn disc(&mut self, radius: f64, y: f64, up: bool, segments: usize) {
+        let normal = Vector::from([0.0, if up { 1.0 } else { -1.0 }, 0.0]);
+        let center = self.vertex([0.0, y, 0.0].into(), [0.5, 0.5].into(), normal);
+        let start = self.vertices.len();
+        for j in 0..segments {
+            let direction = direction(j as f64 / segments as f64);
+            let uv = [0.5 + direction[0] / 2.0, 0.5 + direction[2] / 2.0];
+            self.vertex(
+                direction * radius + Vector::from([0.0, y, 0.0]),
+                uv.into(),
+                normal,
+            );
+        }
+        for j in 0..segments {
+            let (a, b) = (start + j, start + (j + 1) % segments);
+            let (a, b) = if up { (a, b) } else { (b, a) };
+            self.triangle([(center, center), (a, a), (b, b)]);
+        }
+    }
+
+    fn build(self, name: &str) -> Mesh {
+        let materials = [(0..self.indices.len(), "none".into())];
+        let mut mesh = Mesh::new(
+            self.vertices,
+            self.uvs,
+            self.normals,
+            &self.indices,
+            &materials,
+        );
+        mesh.name = name.into();
+        mesh
+    }
+}
+
+/// Horizontal direction `turn` of a full turn around the y axis, starting at `+z` towards `+x`.
+#[inline]
+fn direction(turn: f64) -> Vector<3> {
+    let (sin, cos) = (2.0 * PI * turn).sin_cos();
+    [sin, 0.0, cos].into()
+}
+
+/// Sphere split into `segments` slices around the y axis and `rings` stacks from pole to pole.
+pub fn uv_sphere(radius: f64, segments: usize, rings: usize) -> Mesh {
+    let (segments, rings) = (segments.max(3), rings.max(2));
+    let profile = (0..=rings)
+        .map(|i| {
+            let (sin, cos) = (PI * i as f64 / rings as f64).sin_cos();
+            (radius * sin, radius * cos, sin, cos)
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder::default();
+    builder.lathe(&profile, segments);
+    builder.build("uv_sphere")
+}
+
+/// Sphere made by splitting every face of an icosahedron into four, `subdivisions` times.
+pub fn ico_sphere(radius: f64, subdivisions: usize) -> Mesh {
+    let t = (1.0 + 5f64.sqrt()) / 2.0;
+    let mut points = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .map(|point| Vector::from(point).normalize())
+    .to_vec();
+    let mut faces = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges shared by two faces get a single midpoint.
+        let mut midpoints = HashMap::<(usize, usize), usize>::new();
+        let mut midpoint = |a: usize, b: usize| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                points.push(((points[a] + points[b]) * 0.5).normalize());
+                points.len() - 1
+            })
+        };
+        faces = faces
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut builder = Builder {
+        vertices: points.iter().map(|&point| point * radius).collect(),
+        normals: points.clone(),
+        ..Default::default()
+    };
+    for face in faces {
+        let mut uvs = face.map(|i| {
+            let [x, y, z] = *points[i];
+            [
+                0.5 + x.atan2(z) / (2.0 * PI),
+                y.clamp(-1.0, 1.0).acos() / PI,
+            ]
+        });
+        // Faces crossing the seam at the back would wrap around the whole texture.
+        if uvs.iter().any(|uv| uv[0] > 0.75) && uvs.iter().any(|uv| uv[0] < 0.25) {
+            for uv in uvs.iter_mut().filter(|uv| uv[0] < 0.5) {
+                uv[0] += 1.0;
+            }
+        }
+        // Poles have no longitude of their own, take the one of the rest of the face.
+        for i in 0..3 {
+            let [x, _, z] = *points[face[i]];
+            if x.abs() < 1e-12 && z.abs() < 1e-12 {
+                uvs[i][0] = (uvs[(i + 1) % 3][0] + uvs[(i + 2) % 3][0]) / 2.0;
+            }
+        }
+        let corners = std::array::from_fn(|i| {
+            builder.uvs.push(uvs[i].into());
+            (face[i], builder.uvs.len() - 1)
+        });
+        builder.triangle(corners);
+    }
+    builder.build("ico_sphere")
+}
+
+/// Flat grid in the xz plane facing `+y`, split into `columns` along x and `rows` along z.
+pub fn plane(width: f64, depth: f64, columns: usize, rows: usize) -> Mesh {
+    let (columns, rows) = (columns.max(1), rows.max(1));
+    let mut builder = Builder::default();
+    for i in 0..=rows {
+        for j in 0..=columns {
+            let (u, v) = (j as f64 / columns as f64, i as f64 / rows as f64);
+            builder.vertex(
+                [(u - 0.5) * width, 0.0, (v - 0.5) * depth].into(),
+                [u, v].into(),
+                [0.0, 1.0, 0.0].into(),
+            );
+        }
+    }
+
+    let index = |i: usize, j: usize| i * (columns + 1) + j;
+    for i in 0..rows {
+        for j in 0..columns {
+            builder.quad(
+                index(i, j),
+                index(i + 1, j),
+                index(i + 1, j + 1),
+                index(i, j + 1),
+            );
+        }
+    }
+    builder.build("plane")
+}
+
+/// Closed cylinder along the y axis.
+pub fn cylinder(radius: f64, height: f64, segments: usize) -> Mesh {
+    let segments = segments.max(3);
+    let half = height / 2.0;
+
+    let mut builder = Builder::default();
+    builder.lathe(
+        &[(radius, half, 1.0, 0.0), (radius, -half, 1.0, 0.0)],
+        segments,
+    );
+    builder.disc(radius, half, true, segments);
+    builder.disc(radius, -half, false, segments);
+    builder.build("cylinder")
+}
+
+/// Closed cone along the y axis, with the apex at the top.
+pub fn cone(radius: f64, height: f64, segments: usize) -> Mesh {
+    let segments = segments.max(3);
+    let half = height / 2.0;
+    // Perpendicular to the slope going from the apex down to the base.
+    let normal = Vector::from([height, radius]).normalize();
+
+    let mut builder = Builder::default();
+    builder.lathe(
+        &[
+            (0.0, half, normal[0], normal[1]),
+            (radius, -half, normal[0], normal[1]),
+        ],
+        segments,
+    );
+    builder.disc(radius, -half, false, segments);
+    builder.build("cone")
+}
+
+/// Torus around the y axis, `major_radius` from its center to the middle of the tube.
+pub fn torus(
+    major_radius: f64,
+    minor_radius: f64,
+    major_segments: usize,
+    minor_segments: usize,
+) -> Mesh {
+    let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+    let profile = (0..=minor_segments)
+        .map(|i| {
+            let (sin, cos) = (2.0 * PI * i as f64 / minor_segments as f64).sin_cos();
+            (
+                major_radius + minor_radius * sin,
+                minor_radius * cos,
+                sin,
+                cos,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder::default();
+    builder.lathe(&profile, major_segments);
+    builder.build("torus")
+}
+
+/// Cylinder of `height` along the y axis capped by hemispheres, each split into `rings` stacks.
+pub fn capsule(radius: f64, height: f64, segments: usize, rings: usize) -> Mesh {
+    let (segments, rings) = (segments.max(3), rings.max(1));
+    let half = height / 2.0;
+    let hemisphere = |range: std::ops::RangeInclusive<usize>, center: f64| {
+        range.map(move |i| {
+            let (sin, cos) = (PI / 2.0 * i as f64 / rings as f64).sin_cos();
+            (radius * sin, center + radius * cos, sin, cos)
+        })
+    };
+    let profile = hemisphere(0..=rings, half)
+        .chain(hemisphere(rings..=2 * rings, -half))
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder::default();
+    builder.lathe(&profile, segments);
+    builder.build("capsule")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every face is wound counter-clockwise seen from the side its normals point to.
+    fn assert_facing_normals(mesh: &Mesh) {
+        for &((a, _, na), (b, _, nb), (c, _, nc)) in mesh.indices.iter() {
+            let [a, b, c] = [a, b, c].map(|i| mesh.vertices[i]);
+            let normal = mesh.normals[na] + mesh.normals[nb] + mesh.normals[nc];
+            assert!((b - a).cross(c - a).dot(normal) > 0.0);
+        }
+        for normal in mesh.normals.iter() {
+            assert!((normal.magnitude() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    fn assert_uvs_in_range(mesh: &Mesh) {
+        for uv in mesh.uvs.iter() {
+            assert!((0.0..=1.0).contains(&uv[0]) && (0.0..=1.0).contains(&uv[1]));
+        }
+    }
+
+    #[test]
+    fn test_uv_sphere() {
+        let sphere = uv_sphere(2.0, 8, 4);
+        assert_eq!(sphere.vertices.len(), 9 * 5);
+        // Quads touching the poles are single triangles.
+        assert_eq!(sphere.indices.len(), 2 * 8 * 4 - 2 * 8);
+        assert_facing_normals(&sphere);
+        assert_uvs_in_range(&sphere);
+        for (vertex, normal) in sphere.vertices.iter().zip(sphere.normals.iter()) {
+            assert!((vertex.magnitude() - 2.0).abs() < 1e-9);
+            assert!((*vertex * 0.5 - *normal).magnitude() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_ico_sphere() {
+        let sphere = ico_sphere(1.5, 2);
+        assert_eq!(sphere.vertices.len(), 10 * 16 + 2);
+        assert_eq!(sphere.indices.len(), 20 * 16);
+        assert_facing_normals(&sphere);
+        for vertex in sphere.vertices.iter() {
+            assert!((vertex.magnitude() - 1.5).abs() < 1e-9);
+        }
+        // Faces along the seam do not stretch over the whole texture.
+        for &((_, a, _), (_, b, _), (_, c, _)) in sphere.indices.iter() {
+            let u = [a, b, c].map(|i| sphere.uvs[i][0]);
+            let spread = u.iter().cloned().fold(f64::MIN, f64::max)
+                - u.iter().cloned().fold(f64::MAX, f64::min);
+            assert!(spread < 0.5);
+        }
+    }
+
+    #[test]
+    fn test_plane() {
+        let plane = plane(4.0, 2.0, 4, 2);
+        assert_eq!(plane.vertices.len(), 5 * 3);
+        assert_eq!(plane.indices.len(), 2 * 4 * 2);
+        assert_eq!(plane.vertices[0], (-2.0, 0.0, -1.0).into());
+        assert_eq!(plane.uvs[14], (1.0, 1.0).into());
+        assert_facing_normals(&plane);
+    }
+
+    #[test]
+    fn test_closed_shapes() {
+        for (mesh, faces) in [
+            (cylinder(1.0, 2.0, 6), 2 * 6 + 6 + 6),
+            (cone(1.0, 2.0, 6), 6 + 6),
+            (torus(2.0, 0.5, 8, 6), 2 * 8 * 6),
+            (capsule(0.5, 1.0, 8, 3), 2 * 8 * 7 - 2 * 8),
+        ] {
+            assert_eq!(mesh.indices.len(), faces, "{}", mesh.name);
+            assert_facing_normals(&mesh);
+            assert_uvs_in_range(&mesh);
+        }
+
+        let capsule = capsule(0.5, 1.0, 8, 3);
+        let top = capsule
+            .vertices
+            .iter()
+            .map(|v| v[1])
+            .fold(f64::MIN, f64::max);
+        assert!((top - 1.0).abs() < 1e-9);
+    }
+}
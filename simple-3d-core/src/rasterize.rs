@@ -1,95 +1,583 @@
 use crate::{
     types::{
-        mesh::Indice,
-        pixel::Pixel,
+        light::{Lighting, Shading},
+        material::{FaceCulling, Material},
+        screen::{Fragment, MAX_SAMPLES},
         textures::Texture,
-        triangle::{Triangle, TriangleIterator},
+        triangle::{Triangle, Vertex},
     },
     vector::Vector,
 };
 
-pub fn rasterize<'a, I>(
-    texture: &'a Texture,
-    triangles: TriangleIterator<'a, I>,
-) -> impl Iterator<Item = ((u32, u32), f32, Pixel)>
-where
-    I: Iterator<Item = &'a Indice>,
-{
-    triangles.flat_map(|triangle| triangle_points(texture, triangle))
+/// Bits of sub-pixel precision vertex positions are snapped to, keeping the edge functions exact.
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL: i64 = 1 << SUBPIXEL_BITS;
+
+/// Edge function `a * x + b * y + c` of a directed triangle edge, in sub-pixel units,
+/// positive on the inside of the triangle.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    a: i64,
+    b: i64,
+    c: i64,
+    /// `0` for top and left edges, which own the pixels centred exactly on them, `-1` otherwise.
+    bias: i64,
 }
 
-pub fn triangle_points(
-    texture: &Texture,
-    triangle @ Triangle(a, b, c): Triangle,
-) -> impl Iterator<Item = ((u32, u32), f32, Pixel)> {
-    #[inline]
-    fn det(a: Vector<3>, b: Vector<3>, c: Vector<3>) -> f64 {
-        a[0] * (b[1] - c[1]) + b[0] * (c[1] - a[1]) + c[0] * (a[1] - b[1])
+impl Edge {
+    fn new((x_0, y_0): (i64, i64), (x_1, y_1): (i64, i64), orientation: i64) -> Self {
+        let (a, b) = ((y_0 - y_1) * orientation, (x_1 - x_0) * orientation);
+        // With y pointing down, the inside is right of a left edge and below a top edge.
+        let top_left = a > 0 || (a == 0 && b > 0);
+        Self {
+            a,
+            b,
+            c: (x_0 * y_1 - x_1 * y_0) * orientation,
+            bias: if top_left { 0 } else { -1 },
+        }
     }
 
-    #[inline]
-    fn bounds(Triangle(a, b, c): &Triangle) -> (u32, u32, u32, u32) {
-        let l = a.position[0].min(b.position[0]).min(c.position[0]).floor() as u32;
-        let r = a.position[0].max(b.position[0]).max(c.position[0]).ceil() as u32;
-        let t = a.position[1].min(b.position[1]).min(c.position[1]).floor() as u32;
-        let b = a.position[1].max(b.position[1]).max(c.position[1]).ceil() as u32;
-        (l, t, r, b)
+    /// Value at the centre of the pixel.
+    fn at(&self, x: i64, y: i64) -> i64 {
+        self.a * (x * SUBPIXEL + SUBPIXEL / 2) + self.b * (y * SUBPIXEL + SUBPIXEL / 2) + self.c
     }
+}
+
+/// Fragments of the pixels with a sample inside the triangle, following the top-left rule so
+/// triangles sharing an edge never both cover a sample on it. Pixels are visited row by row,
+/// stepping the edge functions.
+///
+/// `offsets` are the sample positions within a pixel in sixteenths of a pixel from its centre.
+/// Every fragment is shaded once, at the centre if it is covered and at the first covered sample
+/// otherwise.
+///
+/// Only pixels within `(left, top, right, bottom)` are visited, the right and bottom bounds excluded.
+/// Pixels more transparent than the alpha cutoff of the material are left out.
+///
+/// The lights are worked out once for the face, at its corners or at every pixel, following the
+/// shading of `lighting`.
+pub fn triangle_fragments(
+    material: &Material,
+    texture: &Texture,
+    culling: FaceCulling,
+    &Triangle(a, b, c): &Triangle,
+    (area_left, area_top, area_right, area_bottom): (u32, u32, u32, u32),
+    offsets: &[(i8, i8)],
+    lighting: Lighting,
+) -> impl Iterator<Item = Fragment> {
+    let snap = |position: Vector<3>| {
+        let snap = |value: f64| (value * SUBPIXEL as f64).round() as i64;
+        (snap(position[0]), snap(position[1]))
+    };
+    let (p_a, p_b, p_c) = (snap(a.position), snap(b.position), snap(c.position));
+
+    // Twice the signed area, negative when the corners go counter-clockwise on screen.
+    let det_abc =
+        (p_a.0 * (p_b.1 - p_c.1) + p_b.0 * (p_c.1 - p_a.1) + p_c.0 * (p_a.1 - p_b.1)) as f64;
+    let empty = det_abc == 0.0 || culling.culls(det_abc);
+
+    // Edges are turned so the inside is positive whatever the winding.
+    let orientation = if det_abc < 0.0 { -1 } else { 1 };
+    let area = det_abc.abs();
+    let edges = [
+        Edge::new(p_b, p_c, orientation),
+        Edge::new(p_c, p_a, orientation),
+        Edge::new(p_a, p_b, orientation),
+    ];
+    let inside = move |values: &[i64; 3]| {
+        values
+            .iter()
+            .zip(edges.iter())
+            .all(|(value, edge)| value + edge.bias >= 0)
+    };
+
+    let offsets = offsets
+        .iter()
+        .map(|&(x, y)| (x as i64 * SUBPIXEL / 16, y as i64 * SUBPIXEL / 16))
+        .take(MAX_SAMPLES)
+        .collect::<Vec<_>>();
+    let reach = offsets
+        .iter()
+        .map(|(x, y)| x.abs().max(y.abs()))
+        .max()
+        .unwrap_or(0);
+
+    // First and last pixels with samples within the bounding box and the area.
+    let first = |min: i64| (min - reach - SUBPIXEL / 2 + SUBPIXEL - 1).div_euclid(SUBPIXEL);
+    let last = |max: i64| (max + reach - SUBPIXEL / 2).div_euclid(SUBPIXEL);
+    let (left, right) = (
+        first(p_a.0.min(p_b.0).min(p_c.0)).max(area_left as i64),
+        last(p_a.0.max(p_b.0).max(p_c.0)).min(area_right as i64 - 1),
+    );
+    let (top, bottom) = (
+        first(p_a.1.min(p_b.1).min(p_c.1)).max(area_top as i64),
+        last(p_a.1.max(p_b.1).max(p_c.1)).min(area_bottom as i64 - 1),
+    );
+    let rows = top..=if empty { top - 1 } else { bottom };
+
+    // Depth is linear on screen, the other attributes are weighted by `1 / w` to follow the perspective.
+    let (z_a, z_b, z_c) = (a.position[2], b.position[2], c.position[2]);
+    let perspective = move |[alpha, beta, gamma]: [f64; 3]| {
+        let weights = [alpha * a.inv_w, beta * b.inv_w, gamma * c.inv_w];
+        let inv_w = weights[0] + weights[1] + weights[2];
+        weights.map(|weight| weight / inv_w)
+    };
+
+    // Light at the corners, blended across the face unless it is shaded per pixel.
+    let shade = move |vertex: &Vertex| lighting.shade(material, vertex.world, vertex.normal);
+    let corners = match lighting.shading {
+        Shading::Flat => {
+            let centre = (a.world + b.world + c.world) * (1.0 / 3.0);
+            let flat = lighting.shade(material, centre, a.normal + b.normal + c.normal);
+            Some([flat; 3])
+        }
+        Shading::Gouraud => Some([shade(&a), shade(&b), shade(&c)]),
+        Shading::Phong => None,
+    };
+    // Edge function values moved from the pixel centre by `(x, y)` sub-pixel units.
+    let moved = move |mut values: [i64; 3], (x, y): (i64, i64)| {
+        for (value, edge) in values.iter_mut().zip(edges.iter()) {
+            *value += edge.a * x + edge.b * y;
+        }
+        values
+    };
+
+    let row_starts = edges.map(|edge| edge.at(left, top));
+    rows.scan(row_starts, move |row, y| {
+        let start = *row;
+        *row = moved(*row, (0, SUBPIXEL));
+        Some((y, start))
+    })
+    .flat_map(move |(y, start)| {
+        (left..=right).scan(start, move |values, x| {
+            let current = *values;
+            *values = moved(*values, (SUBPIXEL, 0));
+            Some((x, y, current))
+        })
+    })
+    .filter_map(move |(x, y, centre)| {
+        let mut coverage = 0;
+        let mut depths = [f32::INFINITY; MAX_SAMPLES];
+        let mut shaded = inside(&centre).then_some(centre);
+        for (i, &offset) in offsets.iter().enumerate() {
+            let values = moved(centre, offset);
+            if inside(&values) {
+                let [alpha, beta, gamma] = values.map(|value| value as f64 / area);
+                coverage |= 1 << i;
+                depths[i] = (alpha * z_a + beta * z_b + gamma * z_c) as f32;
+                shaded.get_or_insert(values);
+            }
+        }
+        (coverage != 0).then(|| (x, y, coverage, depths, shaded.unwrap()))
+    })
+    .map(move |(x, y, coverage, depths, values)| {
+        // Texture coordinates at the centre of a pixel, given its edge function values.
+        let uv_at = |values: [i64; 3]| {
+            let [w_a, w_b, w_c] = perspective(values.map(|value| value as f64 / area));
+            a.uv * w_a + b.uv * w_b + c.uv * w_c
+        };
+        let uv = uv_at(values);
+        // How far the texture coordinates move to the neighbouring pixels, picking the mip level.
+        let (dx, dy) = (
+            uv_at(moved(values, (SUBPIXEL, 0))) - uv,
+            uv_at(moved(values, (0, SUBPIXEL))) - uv,
+        );
+
+        // Perspective-correct weights of the corners.
+        let [w_a, w_b, w_c] = perspective(values.map(|value| value as f64 / area));
+        let color = a.color * w_a + b.color * w_b + c.color * w_c;
+        let (light, highlight) = match corners {
+            Some([(d_a, s_a), (d_b, s_b), (d_c, s_c)]) => (
+                d_a * w_a + d_b * w_b + d_c * w_c,
+                s_a * w_a + s_b * w_b + s_c * w_c,
+            ),
+            None => shade(&Vertex {
+                world: a.world * w_a + b.world * w_b + c.world * w_c,
+                normal: a.normal * w_a + b.normal * w_b + c.normal * w_c,
+                ..a
+            }),
+        };
+
+        let tint = light.scaled([color[0], color[1], color[2]].into());
+        let pixel = texture
+            .sample_gradient(&material.sampler, uv, dx, dy)
+            .tinted(tint, material.dissolve * color[3])
+            .highlighted(highlight);
+
+        Fragment {
+            point: (x as u32, y as u32),
+            pixel,
+            coverage,
+            depths,
+        }
+    })
+    .filter(move |fragment| {
+        material
+            .alpha_cutoff
+            .is_none_or(|cutoff| fragment.pixel.3 as f64 >= cutoff * 255.0)
+    })
+}
+
+/// Pixels of the line between two screen-space points, found with Bresenham's algorithm, with the
+/// depth interpolated like on the faces. Both ends are included.
+///
+/// Only pixels within `(left, top, right, bottom)` are returned, the right and bottom bounds excluded.
+/// The line is clipped to the area before it is stepped along, so a long line costs each tile only
+/// the pixels it has there.
+pub fn line_points(
+    from: Vector<3>,
+    to: Vector<3>,
+    (area_left, area_top, area_right, area_bottom): (u32, u32, u32, u32),
+) -> impl Iterator<Item = ((u32, u32), f32)> {
+    let (x_0, y_0) = (from[0].floor() as i64, from[1].floor() as i64);
+    let (x_1, y_1) = (to[0].floor() as i64, to[1].floor() as i64);
+    let (dx, dy) = ((x_1 - x_0).abs(), (y_1 - y_0).abs());
+    let (step_x, step_y) = ((x_1 - x_0).signum(), (y_1 - y_0).signum());
+    let steps = dx.max(dy);
+    let (z_from, z_to) = (from[2], to[2]);
 
-    #[inline]
-    fn points(
-        det_abc: f64,
-        (l, t, r, b): (u32, u32, u32, u32),
-    ) -> Box<dyn Iterator<Item = (u32, u32)>> {
-        if det_abc.abs() < f64::EPSILON {
-            Box::new(std::iter::empty())
+    // How far along an axis `length` pixels long the line is after `step` steps, which is what
+    // Bresenham's error term works out to, with ties rounded away from the start.
+    let offset = move |length: i64, step: i64| {
+        if steps == 0 {
+            0
         } else {
-            Box::new((l..=r).flat_map(move |x| (t..=b).map(move |y| (x, y))))
+            (2 * length * step + steps) / (2 * steps)
         }
+    };
+    // Steps that can be within `low..high` on an axis, like the entering and leaving parameters of
+    // Liang-Barsky clipping. Rounding may keep a step at either end too many, the filter drops those.
+    let visible = move |start: i64, sign: i64, length: i64, (low, high): (u32, u32)| {
+        let (low, high) = (low as i64 - start, high as i64 - 1 - start);
+        let (low, high) = if sign < 0 { (-high, -low) } else { (low, high) };
+        if length == 0 {
+            return if (low..=high).contains(&0) {
+                (0, steps)
+            } else {
+                (1, 0)
+            };
+        }
+        (
+            ((low - 1) * steps).div_euclid(length),
+            ((high + 1) * steps + length - 1).div_euclid(length),
+        )
+    };
+    let (first_x, last_x) = visible(x_0, step_x, dx, (area_left, area_right));
+    let (first_y, last_y) = visible(y_0, step_y, dy, (area_top, area_bottom));
+    let (first, last) = (first_x.max(first_y).max(0), last_x.min(last_y).min(steps));
+
+    (first..=last)
+        .map(move |step| {
+            let x = x_0 + step_x * offset(dx, step);
+            let y = y_0 + step_y * offset(dy, step);
+            ((x, y), step)
+        })
+        .filter(move |&((x, y), _)| {
+            (area_left as i64..area_right as i64).contains(&x)
+                && (area_top as i64..area_bottom as i64).contains(&y)
+        })
+        .map(move |((x, y), step)| {
+            let t = if steps == 0 {
+                0.0
+            } else {
+                step as f64 / steps as f64
+            };
+            let z = z_from + (z_to - z_from) * t;
+            ((x as u32, y as u32), z as f32)
+        })
+}
+
+/// Pixels of the edges of the triangle, nothing if it is culled.
+pub fn triangle_edges(
+    culling: FaceCulling,
+    &Triangle(a, b, c): &Triangle,
+    area: (u32, u32, u32, u32),
+) -> impl Iterator<Item = ((u32, u32), f32)> {
+    let (a, b, c) = (a.position, b.position, c.position);
+    let det_abc = a[0] * (b[1] - c[1]) + b[0] * (c[1] - a[1]) + c[0] * (a[1] - b[1]);
+    let edges = if culling.culls(det_abc) {
+        Vec::new()
+    } else {
+        vec![(a, b), (b, c), (c, a)]
+    };
+    edges
+        .into_iter()
+        .flat_map(move |(from, to)| line_points(from, to, area))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn fragments(
+        culling: FaceCulling,
+        corners: [(f64, f64); 3],
+        offsets: &[(i8, i8)],
+    ) -> Vec<Fragment> {
+        let [a, b, c] = corners.map(|(x, y)| {
+            Vertex::new(
+                (x, y, 0.5),
+                (0.0, 0.0),
+                (0.0, 0.0, 1.0),
+                (1.0, 1.0, 1.0, 1.0),
+            )
+        });
+        triangle_fragments(
+            &Material::new("none"),
+            &Texture::None,
+            culling,
+            &Triangle(a, b, c),
+            (0, 0, u32::MAX, u32::MAX),
+            offsets,
+            Lighting::unlit(),
+        )
+        .collect()
     }
 
-    let det_abc = det(a.position, b.position, c.position);
-    points(det_abc, bounds(&triangle)).filter_map(move |(x, y)| {
-        let p = (x as f64, y as f64, 0.0).into();
-        let det_abp = det(a.position, b.position, p);
-        let det_bcp = det(b.position, c.position, p);
-        let det_cap = det(c.position, a.position, p);
+    fn points(culling: FaceCulling, corners: [(f64, f64); 3]) -> Vec<(u32, u32)> {
+        fragments(culling, corners, &[(0, 0)])
+            .iter()
+            .map(|fragment| fragment.point)
+            .collect()
+    }
 
-        let alpha = det_bcp / det_abc;
-        let beta = det_cap / det_abc;
-        let gamma = det_abp / det_abc;
+    #[test]
+    fn test_watertight() {
+        // A fan around an off-grid centre, its edges crossing pixel centres at all sorts of places.
+        let centre = (4.3, 3.7);
+        let outline = [(0.0, 0.0), (8.0, 0.0), (8.0, 8.0), (0.0, 8.0), (0.0, 0.0)];
+        let mut hits = HashMap::<(u32, u32), u32>::new();
+        for pair in outline.windows(2) {
+            for point in points(FaceCulling::NONE, [centre, pair[0], pair[1]]) {
+                *hits.entry(point).or_default() += 1;
+            }
+        }
+        assert_eq!(hits.len(), 64);
+        assert!(hits.values().all(|&count| count == 1));
+    }
 
-        const EPSILON: f64 = 1e-10;
-        if alpha >= -EPSILON && beta >= -EPSILON && gamma >= -EPSILON {
-            let z_a = 1.0 / a.position[2];
-            let z_b = 1.0 / b.position[2];
-            let z_c = 1.0 / c.position[2];
+    #[test]
+    fn test_near_plane() {
+        use crate::{
+            clip::Clipping,
+            types::{
+                camera::{Camera, CameraProperties},
+                screen::Screen,
+                triangle::TriangleIterator,
+            },
+        };
 
-            let inv_z = alpha * z_a + beta * z_b + gamma * z_c;
-            let z = 1.0 / inv_z;
+        // A floor running from behind the camera to far in front of it, coloured by its x and z.
+        let (near, far) = (0.1, 100.0);
+        let properties = CameraProperties::new(std::f64::consts::FRAC_PI_2, 1.0, near, far);
+        let camera = Camera::looking_at(
+            properties,
+            (0.0, 0.0, 0.0),
+            (0.0, 0.0, -1.0),
+            (0.0, 1.0, 0.0),
+        );
+        let corners: [Vector<3>; 4] = [
+            (-50.0, -1.0, 1.0).into(),
+            (50.0, -1.0, 1.0).into(),
+            (50.0, -1.0, -50.0).into(),
+            (-50.0, -1.0, -50.0).into(),
+        ];
+        let color =
+            |p: Vector<3>| Vector::from([(p[0] + 50.0) / 100.0, (1.0 - p[2]) / 51.0, 0.0, 1.0]);
+        let vertices = corners
+            .iter()
+            .map(|&p| (camera.transformation_matrix().dot(&p.homogenous()), p))
+            .collect::<Vec<_>>();
+        let colors = corners.map(color);
+        let uvs = [(0.0, 0.0).into()];
+        let indices = [
+            ((0, 0, 0), (1, 0, 0), (2, 0, 0)),
+            ((0, 0, 0), (2, 0, 0), (3, 0, 0)),
+        ];
+        let triangles = TriangleIterator::new(
+            vertices,
+            &uvs,
+            vec![(0.0, 1.0, 0.0).into()],
+            &colors,
+            indices.iter(),
+            Screen::new(32, 32).transformation_matrix().clone(),
+            Clipping::All,
+        );
 
-            let u_over_z =
-                alpha * (a.uv[0] * z_a) + beta * (b.uv[0] * z_b) + gamma * (c.uv[0] * z_c);
-            let v_over_z =
-                alpha * (a.uv[1] * z_a) + beta * (b.uv[1] * z_b) + gamma * (c.uv[1] * z_c);
+        let mut hits = HashMap::<(u32, u32), Fragment>::new();
+        for triangle in triangles {
+            for fragment in triangle_fragments(
+                &Material::new("none"),
+                &Texture::Solid(255, 255, 255, 255),
+                FaceCulling::NONE,
+                &triangle,
+                (0, 0, 32, 32),
+                &[(0, 0)],
+                Lighting::unlit(),
+            ) {
+                assert!(hits.insert(fragment.point, fragment).is_none());
+            }
+        }
 
-            let u = u_over_z / inv_z;
-            let v = v_over_z / inv_z;
+        // Every pixel below the horizon, each once, with the colour and depth of the floor there.
+        assert_eq!(hits.len(), 16 * 32);
+        for ((x, y), fragment) in hits {
+            let (x_ndc, y_ndc) = ((x as f64 + 0.5) / 16.0 - 1.0, 1.0 - (y as f64 + 0.5) / 16.0);
+            let distance = -1.0 / y_ndc;
+            // The camera has world x growing to the left of the screen.
+            let expected = color((-x_ndc * distance, -1.0, -distance).into());
+            for (actual, expected) in [fragment.pixel.0, fragment.pixel.1].iter().zip(*expected) {
+                assert!((*actual as f64 - expected * 255.0).abs() <= 1.0, "{x} {y}");
+            }
 
-            let tex_width = texture.width() as f64;
-            let tex_height = texture.height() as f64;
+            let z = ((near + far) / (near - far) * -distance + 2.0 * near * far / (near - far))
+                / distance;
+            // The corners are snapped to sub-pixels, which tilts the far end of the floor a little.
+            assert!((fragment.depths[0] as f64 - z).abs() < 1e-4, "{x} {y}");
+        }
+    }
 
-            let tex_x = (u.clamp(0.0, 1.0) * (tex_width - 1.0)).round() as u32;
-            let tex_y = (v.clamp(0.0, 1.0) * (tex_height - 1.0)).round() as u32;
+    #[test]
+    fn test_depth_plane() {
+        use crate::types::screen::Screen;
 
-            let tex_x = tex_x.min(texture.width() - 1);
-            let tex_y = tex_y.min(texture.height() - 1);
+        // Depth is linear on screen, so every sample lies on the plane through the corners.
+        let plane = |x: f64, y: f64| 0.2 + 0.01 * x + 0.02 * y;
+        let [a, b, c] = [(1.0, 1.0), (30.0, 4.0), (8.0, 28.0)].map(|(x, y)| {
+            Vertex::new(
+                (x, y, plane(x, y)),
+                (0.0, 0.0),
+                (0.0, 0.0, 1.0),
+                (1.0, 1.0, 1.0, 1.0),
+            )
+        });
+        let offsets = Screen::multisampled(1, 1, 4).sample_offsets();
+        let fragments = triangle_fragments(
+            &Material::new("none"),
+            &Texture::None,
+            FaceCulling::NONE,
+            &Triangle(a, b, c),
+            (0, 0, 32, 32),
+            offsets,
+            Lighting::unlit(),
+        )
+        .collect::<Vec<_>>();
+        assert!(fragments.len() > 100);
+        for fragment in fragments {
+            let (x, y) = fragment.point;
+            for (i, &(dx, dy)) in offsets.iter().enumerate() {
+                if fragment.coverage & 1 << i != 0 {
+                    let expected = plane(
+                        x as f64 + 0.5 + dx as f64 / 16.0,
+                        y as f64 + 0.5 + dy as f64 / 16.0,
+                    );
+                    assert!((fragment.depths[i] as f64 - expected).abs() < 1e-6);
+                }
+            }
+        }
+    }
 
-            let pixel = texture.get(tex_x, tex_y);
+    #[test]
+    fn test_pixel_centres() {
+        // Covers the corner of pixel (0, 0) but not its centre.
+        assert!(points(FaceCulling::NONE, [(0.0, 0.0), (0.4, 0.0), (0.0, 0.4)]).is_empty());
+        assert_eq!(
+            points(FaceCulling::NONE, [(0.4, 0.4), (0.7, 0.4), (0.4, 0.7)]),
+            [(0, 0)]
+        );
 
-            Some(((x, y), z as f32, pixel))
-        } else {
-            None
+        // Row by row, left to right, leaving the centres on the bottom right edge to the next triangle.
+        let corner = points(FaceCulling::NONE, [(0.0, 0.0), (3.0, 0.0), (0.0, 3.0)]);
+        assert_eq!(corner, [(0, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_culling() {
+        // Counter-clockwise as seen on the screen, with y pointing down.
+        let front = [(0.0, 0.0), (0.0, 4.0), (4.0, 0.0)];
+        let back = [front[0], front[2], front[1]];
+        assert!(!points(FaceCulling::default(), front).is_empty());
+        assert!(points(FaceCulling::default(), back).is_empty());
+    }
+
+    #[test]
+    fn test_multisample_coverage() {
+        // Two halves of pixel (0, 0), split along the diagonal through its centre.
+        let offsets = [(-2, -6), (6, -2), (-6, 2), (2, 6)];
+        let upper = fragments(
+            FaceCulling::NONE,
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            &offsets,
+        );
+        let lower = fragments(
+            FaceCulling::NONE,
+            [(1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+            &offsets,
+        );
+        let ([upper], [lower]) = (&upper[..], &lower[..]) else {
+            panic!("expected a fragment from each half");
+        };
+        assert_eq!((upper.coverage, lower.coverage), (0b0101, 0b1010));
+        assert_eq!(upper.depths[0], 0.5);
+    }
+
+    #[test]
+    fn test_line_points() {
+        let line = |from: (f64, f64), to: (f64, f64)| {
+            line_points(
+                [from.0, from.1, 0.5].into(),
+                [to.0, to.1, 0.5].into(),
+                (0, 0, 10, 10),
+            )
+            .map(|(point, _)| point)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            line((0.5, 0.5), (4.5, 2.5)),
+            [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            line((2.5, 3.5), (2.5, 0.5)),
+            [(2, 3), (2, 2), (2, 1), (2, 0)]
+        );
+        assert_eq!(line((1.5, 1.5), (1.5, 1.5)), [(1, 1)]);
+        // Cut to the area, leaving the pixels past the right edge out.
+        assert_eq!(line((7.5, 0.5), (12.5, 0.5)), [(7, 0), (8, 0), (9, 0)]);
+
+        // Clipped to tiles, the pieces are the pixels of the whole line.
+        for (from, to) in [((1.5, 2.5), (38.5, 17.5)), ((30.5, 1.5), (3.5, 39.5))] {
+            let whole = line_points(
+                [from.0, from.1, 0.5].into(),
+                [to.0, to.1, 0.5].into(),
+                (0, 0, 40, 40),
+            )
+            .map(|(point, _)| point)
+            .collect::<Vec<_>>();
+            let mut tiled = (0..40)
+                .step_by(8)
+                .flat_map(|top| (0..40).step_by(8).map(move |left| (left, top)))
+                .flat_map(|(left, top)| {
+                    line_points(
+                        [from.0, from.1, 0.5].into(),
+                        [to.0, to.1, 0.5].into(),
+                        (left, top, left + 8, top + 8),
+                    )
+                })
+                .map(|(point, _)| point)
+                .collect::<Vec<_>>();
+            tiled.sort_by_key(|point| whole.iter().position(|p| p == point));
+            assert_eq!(tiled, whole);
         }
-    })
+
+        let depths = line_points(
+            [0.5, 0.5, 0.5].into(),
+            [2.5, 0.5, 0.25].into(),
+            (0, 0, 10, 10),
+        )
+        .map(|(_, z)| z)
+        .collect::<Vec<_>>();
+        assert_eq!(depths, [0.5, 0.375, 0.25]);
+    }
 }
@@ -0,0 +1,315 @@
+//! Binned tile renderer: triangles are sorted into screen tiles, which are then shaded in parallel.
+
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    thread::{self, available_parallelism},
+};
+
+use crate::{
+    rasterize,
+    types::{
+        light::Lighting,
+        material::{Blend, FaceCulling, Material, RenderMode},
+        pixel::Pixel,
+        screen::{Band, Fragment, Screen},
+        textures::Texture,
+        triangle::Triangle,
+    },
+};
+
+/// Width and height of a tile in pixels.
+const TILE_SIZE: u32 = 64;
+
+/// Colour of the edges drawn in the wireframe modes.
+const LINE_COLOR: Pixel = Pixel(0, 0, 0, 255);
+/// How much closer edges are drawn than their faces, so the faces do not hide them.
+const LINE_DEPTH_BIAS: f32 = 1e-4;
+
+/// Screen-space triangle with everything needed to shade it.
+pub type Primitive<'a> = (Triangle, &'a Material, &'a Texture, FaceCulling, RenderMode);
+
+/// Draws the primitives to the screen, in the same order on every tile so the result does not
+/// depend on the number of threads.
+///
+/// Opaque primitives are drawn first in the given order, then transparent ones from back to front,
+/// as they do not write depth.
+pub fn render(screen: &mut Screen, primitives: &[Primitive], lighting: Lighting) {
+    let (width, height) = screen.size();
+    // Minimised windows have nothing to draw to, nor tiles to bin into.
+    if width == 0 || height == 0 {
+        return;
+    }
+    let (columns, rows) = (width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE));
+
+    let depth = |Triangle(a, b, c): &Triangle| a.position[2] + b.position[2] + c.position[2];
+    let mut order = (0..primitives.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| {
+        let ((first, first_material, ..), (second, second_material, ..)) =
+            (&primitives[i], &primitives[j]);
+        match (
+            first_material.is_transparent(),
+            second_material.is_transparent(),
+        ) {
+            (true, true) => depth(second).total_cmp(&depth(first)),
+            (first, second) => first.cmp(&second),
+        }
+    });
+
+    let mut bins = vec![Vec::<usize>::new(); (columns * rows) as usize];
+    for index in order {
+        let Triangle(a, b, c) = &primitives[index].0;
+        let tile = |position: f64, tiles: u32| {
+            (position.max(0.0) as u32 / TILE_SIZE).min(tiles.saturating_sub(1))
+        };
+        let (x, y) = (
+            [a, b, c].map(|v| v.position[0]),
+            [a, b, c].map(|v| v.position[1]),
+        );
+        let (left, right) = (
+            tile(x[0].min(x[1]).min(x[2]), columns),
+            tile(x[0].max(x[1]).max(x[2]), columns),
+        );
+        let (top, bottom) = (
+            tile(y[0].min(y[1]).min(y[2]), rows),
+            tile(y[0].max(y[1]).max(y[2]), rows),
+        );
+        for row in top..=bottom {
+            for column in left..=right {
+                bins[(row * columns + column) as usize].push(index);
+            }
+        }
+    }
+
+    let offsets = screen.sample_offsets();
+    let bands = Mutex::new(screen.bands_mut(TILE_SIZE).enumerate());
+    let work = || {
+        loop {
+            let Some((row, mut band)) = bands.lock().unwrap().next() else {
+                break;
+            };
+            for column in 0..columns {
+                let bin = &bins[row * columns as usize + column as usize];
+                draw_tile(&mut band, column, primitives, bin, offsets, lighting);
+            }
+        }
+    };
+
+    let threads = available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(rows as usize);
+    if threads <= 1 {
+        work();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(work);
+            }
+        });
+    }
+}
+
+fn draw_tile(
+    band: &mut Band,
+    column: u32,
+    primitives: &[Primitive],
+    bin: &[usize],
+    offsets: &[(i8, i8)],
+    lighting: Lighting,
+) {
+    let (top, bottom) = band.rows();
+    let area = (column * TILE_SIZE, top, (column + 1) * TILE_SIZE, bottom);
+    for &index in bin {
+        let (triangle, material, texture, culling, mode) = &primitives[index];
+        if *mode == RenderMode::Wireframe {
+            continue;
+        }
+        let fragments = rasterize::triangle_fragments(
+            material, texture, *culling, triangle, area, offsets, lighting,
+        );
+        for mut fragment in fragments {
+            if *mode == RenderMode::HiddenLine {
+                fragment.pixel = Pixel::default();
+                band.draw(&fragment, Blend::Opaque);
+            } else {
+                band.draw(&fragment, material.blend);
+            }
+        }
+    }
+
+    // Edges go last, so every face of the tile can hide them.
+    for &index in bin {
+        let (triangle, _, _, culling, mode) = &primitives[index];
+        if *mode == RenderMode::Shaded {
+            continue;
+        }
+        for (point, z) in rasterize::triangle_edges(*culling, triangle, area) {
+            // Plain wireframes show through everything, the other modes only where no face is in front.
+            let z = if *mode == RenderMode::Wireframe {
+                f32::MIN
+            } else {
+                z - LINE_DEPTH_BIAS
+            };
+            band.draw(&Fragment::covering(point, z, LINE_COLOR), Blend::Opaque);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::triangle::Vertex;
+
+    #[test]
+    fn test_render_matches_single_pass() {
+        let material = Material::new("none");
+        let textures = [
+            Texture::Solid(255, 0, 0, 255),
+            Texture::Solid(0, 0, 255, 255),
+        ];
+        // Overlapping triangles at equal depth spanning several tiles, plus some off screen.
+        let primitives = [
+            [(-20.0, -10.0), (150.0, 30.0), (40.0, 170.0)],
+            [(10.0, 5.0), (200.0, 100.0), (100.0, 120.0)],
+            [(70.0, 60.0), (60.0, 130.0), (140.0, 80.0)],
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, corners)| {
+            let [a, b, c] = corners.map(|(x, y)| {
+                Vertex::new(
+                    (x, y, 0.5),
+                    (0.0, 0.0),
+                    (0.0, 0.0, 1.0),
+                    (1.0, 1.0, 1.0, 1.0),
+                )
+            });
+            (
+                Triangle(a, b, c),
+                &material,
+                &textures[i % 2],
+                FaceCulling::NONE,
+                RenderMode::Shaded,
+            )
+        })
+        .collect::<Vec<_>>();
+
+        let mut screen = Screen::new(150, 130);
+        render(&mut screen, &primitives, Lighting::unlit());
+        // Nothing to draw on an empty screen.
+        render(&mut Screen::new(0, 130), &primitives, Lighting::unlit());
+        render(&mut Screen::new(150, 0), &primitives, Lighting::unlit());
+
+        let mut expected = Screen::new(150, 130);
+        for (triangle, material, texture, culling, _) in primitives.iter() {
+            let area = (0, 0, 150, 130);
+            let offsets = expected.sample_offsets();
+            for fragment in rasterize::triangle_fragments(
+                material,
+                texture,
+                *culling,
+                triangle,
+                area,
+                offsets,
+                Lighting::unlit(),
+            ) {
+                expected.draw(&fragment, material.blend);
+            }
+        }
+        assert!(screen.buffer() == expected.buffer());
+        assert!(screen.buffer().contains(&Pixel(0, 0, 255, 255)));
+    }
+
+    #[test]
+    fn test_render_transparent() {
+        let (opaque, glass) = (
+            Material::new("opaque"),
+            Material {
+                blend: Blend::Alpha,
+                dissolve: 0.5,
+                ..Material::new("glass")
+            },
+        );
+        let (black, white) = (
+            Texture::Solid(0, 0, 0, 255),
+            Texture::Solid(255, 255, 255, 255),
+        );
+        let square = |z: f64| {
+            [
+                [(0.0, 0.0), (8.0, 0.0), (0.0, 8.0)],
+                [(8.0, 0.0), (8.0, 8.0), (0.0, 8.0)],
+            ]
+            .map(|corners| {
+                let [a, b, c] = corners.map(|(x, y)| {
+                    Vertex::new((x, y, z), (0.0, 0.0), (0.0, 0.0, 1.0), (1.0, 1.0, 1.0, 1.0))
+                });
+                Triangle(a, b, c)
+            })
+        };
+        // Glass in front of glass in front of an opaque square, given front to back.
+        let primitives = [
+            (square(0.2), &glass, &white),
+            (square(0.4), &glass, &black),
+            (square(0.6), &opaque, &black),
+        ]
+        .into_iter()
+        .flat_map(|(triangles, material, texture)| {
+            triangles.map(|triangle| {
+                (
+                    triangle,
+                    material,
+                    texture,
+                    FaceCulling::NONE,
+                    RenderMode::Shaded,
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+
+        let mut screen = Screen::new(8, 8);
+        render(&mut screen, &primitives, Lighting::unlit());
+        // Black, then black glass over it, then white glass over that.
+        assert!(
+            screen
+                .buffer()
+                .iter()
+                .all(|&pixel| pixel == Pixel(128, 128, 128, 255))
+        );
+    }
+
+    #[test]
+    fn test_render_hidden_line() {
+        let material = Material::new("none");
+        let triangle = |corners: [(f64, f64); 3], z: f64, mode: RenderMode| {
+            let [a, b, c] = corners.map(|(x, y)| {
+                Vertex::new((x, y, z), (0.0, 0.0), (0.0, 0.0, 1.0), (1.0, 1.0, 1.0, 1.0))
+            });
+            (
+                Triangle(a, b, c),
+                &material,
+                &Texture::None,
+                FaceCulling::NONE,
+                mode,
+            )
+        };
+        let draw = |mode: RenderMode| {
+            // A square split along the diagonal through (8, 7), behind a triangle covering that pixel.
+            let primitives = [
+                triangle([(0.5, 0.5), (15.5, 0.5), (0.5, 15.5)], 0.6, mode),
+                triangle([(15.5, 0.5), (15.5, 15.5), (0.5, 15.5)], 0.6, mode),
+                triangle([(2.5, 2.5), (14.5, 2.5), (2.5, 14.5)], 0.2, mode),
+            ];
+            let mut screen = Screen::new(16, 16);
+            render(&mut screen, &primitives, Lighting::unlit());
+            let pixel = |x: usize, y: usize| screen.buffer()[x + y * 16];
+            (pixel(0, 0), pixel(8, 7))
+        };
+
+        assert_eq!(draw(RenderMode::Wireframe), (LINE_COLOR, LINE_COLOR));
+        assert_eq!(draw(RenderMode::HiddenLine), (LINE_COLOR, Pixel::default()));
+        assert_eq!(
+            draw(RenderMode::Shaded),
+            (Pixel::default(), Pixel::default())
+        );
+    }
+}
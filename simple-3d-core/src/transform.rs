@@ -1,35 +1,96 @@
-use std::slice::Iter;
+use std::{rc::Rc, slice::Iter};
 
-use crate::types::{
-    matrix::Matrix,
-    mesh::{Indice, Mesh},
-    textures::{Texture, Textures},
-    triangle::{self, TriangleIterator},
+use crate::{
+    clip::Clipping,
+    transformations,
+    types::{
+        material::{FaceCulling, Material, RenderMode},
+        matrix::Matrix,
+        mesh::{Indice, Mesh},
+        textures::{Texture, Textures},
+        triangle::{self, TriangleIterator},
+    },
 };
 
 pub fn transform<'a>(
     textures: &'a Textures,
     objects: &'a [Mesh],
-    camera_viewport_transformation: Matrix<4, 4>,
-) -> impl Iterator<Item = (TriangleIterator<'a, Iter<'a, Indice>>, &'a Texture)> + 'a {
-    objects.iter().map(move |mesh| {
-        let matrix = camera_viewport_transformation.clone() * mesh.transformation_matrix().clone();
+    camera_transformation: Matrix<4, 4>,
+    viewport_transformation: Matrix<4, 4>,
+    clipping: Clipping,
+    render_mode: RenderMode,
+) -> impl Iterator<
+    Item = (
+        TriangleIterator<'a, Iter<'a, Indice>>,
+        &'a Material,
+        &'a Texture,
+        FaceCulling,
+        RenderMode,
+    ),
+> + 'a {
+    objects.iter().flat_map(move |mesh| {
+        let matrix = camera_transformation.clone() * mesh.transformation_matrix().clone();
 
-        let texture = textures.get(&mesh.texture);
-        (transform_mesh(mesh, matrix), texture)
+        let level = mesh.level_of_detail(&(viewport_transformation.clone() * matrix.clone()));
+        // Mirroring turns the faces inside out on screen.
+        let mirrored = transformations::mirrors(mesh.transformation_matrix());
+
+        transform_mesh(
+            level,
+            matrix,
+            mesh.transformation_matrix(),
+            mesh.normal_transformation_matrix(),
+            viewport_transformation.clone(),
+            clipping,
+        )
+        .map(move |(triangles, material)| {
+            let material = textures.material(material);
+            let texture = textures.diffuse(material);
+            let mut culling = mesh.culling.unwrap_or(material.culling);
+            if mirrored {
+                culling.front_face = culling.front_face.reversed();
+            }
+            let render_mode = mesh.render_mode.unwrap_or(render_mode);
+            (triangles, material, texture, culling, render_mode)
+        })
     })
 }
 
 pub fn transform_mesh<'a>(
-    Mesh {
+    mesh @ Mesh {
         vertices,
-        indices,
         uvs,
+        normals,
+        colors,
         ..
     }: &'a Mesh,
     matrix: Matrix<4, 4>,
-) -> TriangleIterator<'a, Iter<'a, Indice>> {
-    let transformed_vertices =
-        triangle::transform(&matrix, vertices.iter().copied()).collect::<Vec<_>>();
-    TriangleIterator::new(&transformed_vertices, uvs, indices)
+    world_matrix: &Matrix<4, 4>,
+    normal_matrix: &Matrix<4, 4>,
+    viewport: Matrix<4, 4>,
+    clipping: Clipping,
+) -> impl Iterator<Item = (TriangleIterator<'a, Iter<'a, Indice>>, &'a str)> {
+    let transformed_vertices = triangle::transform(&matrix, vertices.iter().copied())
+        .zip(
+            vertices
+                .iter()
+                .map(|vertex| vertex.transformed(world_matrix)),
+        )
+        .collect::<Rc<[_]>>();
+    let transformed_normals =
+        triangle::transform_normals(normal_matrix, normals.iter().copied()).collect::<Rc<[_]>>();
+    mesh.material_groups().map(move |(indices, material)| {
+        (
+            TriangleIterator::new(
+                transformed_vertices.clone(),
+                uvs,
+                transformed_normals.clone(),
+                colors,
+                indices,
+                viewport.clone(),
+                clipping,
+            ),
+            material,
+        )
+    })
 }
@@ -8,6 +8,11 @@ pub fn transformation<V: Into<Vector<3>>>(
     translate(translation) * rotate(rotation) * scale(scale_factor)
 }
 
+pub fn normal_transformation<V: Into<Vector<3>>>(scale_factor: V, rotation: V) -> Matrix<4, 4> {
+    let [x, y, z] = *scale_factor.into();
+    rotate(rotation) * scale((1.0 / x, 1.0 / y, 1.0 / z))
+}
+
 pub fn rotate<V: Into<Vector<3>>>(angles: V) -> Matrix<4, 4> {
     let [x, y, z] = *angles.into();
 
@@ -36,6 +41,32 @@ pub fn rotate<V: Into<Vector<3>>>(angles: V) -> Matrix<4, 4> {
     .into()
 }
 
+/// Rotation by a unit quaternion given as `[x, y, z, w]`.
+pub fn rotate_quaternion([x, y, z, w]: [f64; 4]) -> Matrix<4, 4> {
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+    .into()
+}
+
 pub fn translate<V: Into<Vector<3>>>(vector: V) -> Matrix<4, 4> {
     let [x, y, z] = *vector.into();
     [
@@ -58,6 +89,17 @@ pub fn scale<V: Into<Vector<3>>>(vector: V) -> Matrix<4, 4> {
     .into()
 }
 
+/// Whether the transformation mirrors space, which is when its linear part has a negative determinant.
+pub fn mirrors(transformation: &Matrix<4, 4>) -> bool {
+    let column = |i: usize| {
+        let v = transformation.dot(&Vector::from(std::array::from_fn(|j| {
+            (i == j) as u8 as f64
+        })));
+        Vector::from([v[0], v[1], v[2]])
+    };
+    column(0).dot(column(1).cross(column(2))) < 0.0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +177,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rotate_quaternion() {
+        let half = FRAC_PI_2 / 2.0;
+        assert_relative_eq!(
+            rotate_quaternion([0.0, half.sin(), 0.0, half.cos()]),
+            rotate((0.0, FRAC_PI_2, 0.0)),
+            epsilon = 1e-12,
+        );
+        assert_relative_eq!(rotate_quaternion([0.0, 0.0, 0.0, 1.0]), Matrix::identity());
+    }
+
     #[test]
     fn test_translate() {
         assert_eq!(
@@ -163,6 +216,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normal_transformation() {
+        let scale_factor = (2.0, 4.0, 0.5);
+        let rotation = (0.0, FRAC_PI_2, 0.0);
+        assert_relative_eq!(
+            normal_transformation(scale_factor, rotation),
+            rotate(rotation) * scale((0.5, 0.25, 2.0)),
+        );
+    }
+
     #[test]
     fn test_transformation() {
         let scale_factor = (1.0, 2.0, 3.0);
@@ -13,17 +13,27 @@ pub struct Camera {
 
 impl Camera {
     pub fn new(properties: CameraProperties) -> Self {
-        let position = (0.0, 0.0, 5.0).into();
-        let target = (0.0, 0.0, 0.0).into();
-        let up = (0.0, 1.0, 0.0).into();
-
-        let transformation = properties.transformation_matrix().clone()
-            * Self::calculate_view_matrix(position, target, up);
-
+        Self::looking_at(
+            properties,
+            (0.0, 0.0, 5.0),
+            (0.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0),
+        )
+    }
+    /// Camera at `position` looking towards `target`, with `up` pointing to the top of the screen.
+    pub fn looking_at(
+        properties: CameraProperties,
+        position: impl Into<Vector<3>>,
+        target: impl Into<Vector<3>>,
+        up: impl Into<Vector<3>>,
+    ) -> Self {
+        let (position, target, up) = (position.into(), target.into(), up.into());
+        let transformation =
+            Self::calculate_transformation_matrix(&properties, position, target, up);
         Self {
-            position: (0.0, 0.0, 5.0).into(),
-            target: (0.0, 0.0, 0.0).into(),
-            up: (0.0, 1.0, 0.0).into(),
+            position,
+            target,
+            up,
             properties,
             transformation,
         }
@@ -80,6 +90,12 @@ impl Camera {
     pub fn transformation_matrix(&self) -> &Matrix<4, 4> {
         &self.transformation
     }
+    pub fn position(&self) -> Vector<3> {
+        self.position
+    }
+    pub fn target(&self) -> Vector<3> {
+        self.target
+    }
     pub fn radius(&self) -> f64 {
         (self.position - self.target).magnitude()
     }
@@ -155,6 +171,19 @@ impl CameraProperties {
         .into()
     }
 
+    pub fn fov(&self) -> f64 {
+        self.fov
+    }
+    pub fn aspect_ratio(&self) -> f64 {
+        self.aspect_ratio
+    }
+    pub fn near(&self) -> f64 {
+        self.near
+    }
+    pub fn far(&self) -> f64 {
+        self.far
+    }
+
     #[inline]
     fn transformation_matrix(&self) -> &Matrix<4, 4> {
         &self.trasformation
@@ -4,4 +4,25 @@ pub enum Key {
     ArrowDown,
     ArrowLeft,
     ArrowRight,
+    /// Key typing a single character, like `w` or `+`.
+    Char(char),
+}
+
+impl Key {
+    /// Key named like the browser `KeyboardEvent.key` (`ArrowUp`, `w`, ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "ArrowUp" => Key::ArrowUp,
+            "ArrowDown" => Key::ArrowDown,
+            "ArrowLeft" => Key::ArrowLeft,
+            "ArrowRight" => Key::ArrowRight,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Key::Char(c),
+                    _ => return None,
+                }
+            }
+        })
+    }
 }
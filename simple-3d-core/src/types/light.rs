@@ -0,0 +1,271 @@
+use serde::Deserialize;
+
+use super::{material::Material, vector::Vector};
+
+/// Light shining on the scene, its colour scaled by its intensity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    /// Light reaching every face equally, whichever way it faces.
+    Ambient { color: Vector<3> },
+    /// Parallel rays going in `direction`, like sunlight.
+    Directional {
+        direction: Vector<3>,
+        color: Vector<3>,
+    },
+    /// Light going out from `position` in every direction, fading out to nothing at `range`.
+    Point {
+        position: Vector<3>,
+        color: Vector<3>,
+        range: f64,
+    },
+    /// Light going out from `position` in a cone around `direction`, full up to `inner` radians off
+    /// its axis and fading out to nothing at `outer`.
+    Spot {
+        position: Vector<3>,
+        direction: Vector<3>,
+        color: Vector<3>,
+        range: f64,
+        inner: f64,
+        outer: f64,
+    },
+}
+
+impl Light {
+    /// Moves point and spot lights by `delta`, the others have no position.
+    pub fn translate(&mut self, delta: Vector<3>) {
+        if let Light::Point { position, .. } | Light::Spot { position, .. } = self {
+            *position += delta;
+        }
+    }
+
+    /// Direction towards the light from `point` and the colour of the light reaching it, `None`
+    /// for ambient light.
+    fn incoming(&self, point: Vector<3>) -> Option<(Vector<3>, Vector<3>)> {
+        match *self {
+            Light::Ambient { .. } => None,
+            Light::Directional { direction, color } => Some((unit(direction) * -1.0, color)),
+            Light::Point {
+                position,
+                color,
+                range,
+            } => {
+                let (towards, attenuation) = falloff(position - point, range);
+                Some((towards, color * attenuation))
+            }
+            Light::Spot {
+                position,
+                direction,
+                color,
+                range,
+                inner,
+                outer,
+            } => {
+                let (towards, attenuation) = falloff(position - point, range);
+                let cos = (towards * -1.0).dot(unit(direction));
+                let cone = ((cos - outer.cos()) / (inner.cos() - outer.cos()).max(f64::EPSILON))
+                    .clamp(0.0, 1.0);
+                Some((towards, color * (attenuation * cone)))
+            }
+        }
+    }
+}
+
+/// Unit vector along `vector`, or the zero vector as it is, which has no direction to keep.
+fn unit(vector: Vector<3>) -> Vector<3> {
+    if vector.magnitude() > 0.0 {
+        vector.normalize()
+    } else {
+        vector
+    }
+}
+
+/// Unit vector along `towards` and how much of a light `range` away reaches that far.
+fn falloff(towards: Vector<3>, range: f64) -> (Vector<3>, f64) {
+    let distance = towards.magnitude();
+    let attenuation = (1.0 - (distance / range).powi(2)).clamp(0.0, 1.0).powi(2);
+    if distance > 0.0 {
+        (towards * (1.0 / distance), attenuation)
+    } else {
+        (towards, attenuation)
+    }
+}
+
+/// Where the lights are worked out across a face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shading {
+    /// Once per face, at its centre.
+    Flat,
+    /// At the corners, blended across the face.
+    Gouraud,
+    /// At every pixel, with the normal blended across the face.
+    #[default]
+    Phong,
+}
+
+impl Shading {
+    /// The mode after this one, going back to the first after the last.
+    pub fn next(self) -> Self {
+        match self {
+            Shading::Flat => Shading::Gouraud,
+            Shading::Gouraud => Shading::Phong,
+            Shading::Phong => Shading::Flat,
+        }
+    }
+}
+
+/// Lights of the scene seen from `eye`, the position of the camera.
+#[derive(Debug, Clone, Copy)]
+pub struct Lighting<'a> {
+    pub lights: &'a [Light],
+    pub eye: Vector<3>,
+    pub shading: Shading,
+}
+
+impl Lighting<'_> {
+    /// No lights, leaving faces in the colours of their materials.
+    pub fn unlit() -> Self {
+        Lighting {
+            lights: &[],
+            eye: (0.0, 0.0, 0.0).into(),
+            shading: Shading::default(),
+        }
+    }
+
+    /// Lambert diffuse and Blinn-Phong specular light at `position` on a face facing `normal`, in world
+    /// space. The first is what the texture colour is multiplied by, the second is added to it.
+    ///
+    /// Without lights the texture is only multiplied by the diffuse colour of the material.
+    pub fn shade(
+        &self,
+        material: &Material,
+        position: Vector<3>,
+        normal: Vector<3>,
+    ) -> (Vector<3>, Vector<3>) {
+        let black = Vector::from([0.0; 3]);
+        if self.lights.is_empty() {
+            return (material.diffuse, black);
+        }
+
+        let normal = unit(normal);
+        let view = unit(self.eye - position);
+        let (mut diffuse, mut specular) = (black, black);
+        for light in self.lights {
+            if let Light::Ambient { color } = light {
+                diffuse += material.ambient.scaled(*color);
+            }
+            let Some((towards, color)) = light.incoming(position) else {
+                continue;
+            };
+            let lambert = normal.dot(towards);
+            if lambert <= 0.0 {
+                continue;
+            }
+            diffuse += material.diffuse.scaled(color) * lambert;
+            let half = unit(towards + view);
+            let highlight = normal.dot(half).max(0.0).powf(material.shininess);
+            specular += material.specular.scaled(color) * highlight;
+        }
+        (diffuse, specular)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector<3>, expected: [f64; 3]) {
+        assert!(
+            (actual - expected.into()).magnitude() < 1e-9,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_shade() {
+        let material = Material {
+            ambient: (0.5, 0.5, 0.5).into(),
+            specular: (1.0, 1.0, 1.0).into(),
+            shininess: 10.0,
+            ..Material::new("lit")
+        };
+        let up = Vector::from([0.0, 1.0, 0.0]);
+        let lights = [
+            Light::Ambient {
+                color: (0.2, 0.2, 0.2).into(),
+            },
+            // At 60 degrees from the normal, so half of it is taken in.
+            Light::Directional {
+                direction: (-(3f64.sqrt()), -1.0, 0.0).into(),
+                color: (1.0, 0.0, 0.0).into(),
+            },
+        ];
+        let lighting = Lighting {
+            lights: &lights,
+            eye: (-10.0 * 3f64.sqrt(), 10.0, 0.0).into(),
+            shading: Shading::Phong,
+        };
+        let (diffuse, specular) = lighting.shade(&material, [0.0; 3].into(), up);
+        assert_close(diffuse, [0.6, 0.1, 0.1]);
+        // The camera is on the far side of the normal from the light, which is a mirror reflection.
+        assert_close(specular, [1.0, 0.0, 0.0]);
+
+        // Faces turned away only get the ambient light.
+        let (diffuse, specular) = lighting.shade(&material, [0.0; 3].into(), up * -1.0);
+        assert_close(diffuse, [0.1, 0.1, 0.1]);
+        assert_close(specular, [0.0; 3]);
+
+        let (diffuse, _) = Lighting::unlit().shade(&material, [0.0; 3].into(), up);
+        assert_eq!(diffuse, material.diffuse);
+
+        // Shading at the eye or with lights without a direction stays finite.
+        let lights = [
+            Light::Directional {
+                direction: [0.0; 3].into(),
+                color: (1.0, 1.0, 1.0).into(),
+            },
+            Light::Spot {
+                position: (0.0, 1.0, 0.0).into(),
+                direction: [0.0; 3].into(),
+                color: (1.0, 1.0, 1.0).into(),
+                range: 8.0,
+                inner: 0.1,
+                outer: 0.5,
+            },
+        ];
+        let lighting = Lighting {
+            lights: &lights,
+            eye: [0.0; 3].into(),
+            shading: Shading::Phong,
+        };
+        let (diffuse, specular) = lighting.shade(&material, [0.0; 3].into(), up);
+        for value in [diffuse, specular].iter().flat_map(|v| [v[0], v[1], v[2]]) {
+            assert!(value.is_finite());
+        }
+    }
+
+    #[test]
+    fn test_spot_falloff() {
+        let spot = Light::Spot {
+            position: (0.0, 4.0, 0.0).into(),
+            direction: (0.0, -1.0, 0.0).into(),
+            color: (1.0, 1.0, 1.0).into(),
+            range: 8.0,
+            inner: 0.1,
+            outer: 0.5,
+        };
+        let light = |x: f64| spot.incoming((x, 0.0, 0.0).into()).unwrap().1[0];
+        // Half way to the range, on the axis.
+        assert!((light(0.0) - 0.5625).abs() < 1e-12);
+        // Outside the cone.
+        assert_eq!(light(4.0), 0.0);
+
+        let mut point = Light::Point {
+            position: (0.0, 0.0, 0.0).into(),
+            color: (1.0, 1.0, 1.0).into(),
+            range: 1.0,
+        };
+        point.translate((0.0, 2.0, 0.0).into());
+        assert_eq!(point.incoming([0.0; 3].into()).unwrap().1[0], 0.0);
+    }
+}
@@ -0,0 +1,158 @@
+use super::{matrix::Matrix, mesh::Mesh, vector::Vector};
+
+/// Pixels of the projected bounding box each triangle of the level drawn should cover at least.
+const PIXELS_PER_TRIANGLE: f64 = 4.0;
+
+/// Simplified versions of a mesh, drawn instead of it when it covers few pixels on screen.
+#[derive(Debug, Clone)]
+pub struct LodChain {
+    /// Bounding box of the full mesh, `(min, max)`.
+    bounds: (Vector<3>, Vector<3>),
+    /// From the most detailed to the least, each with about a quarter of the faces of the previous one.
+    levels: Box<[Mesh]>,
+}
+
+impl Default for LodChain {
+    fn default() -> Self {
+        Self {
+            bounds: ([0.0; 3].into(), [0.0; 3].into()),
+            levels: Box::new([]),
+        }
+    }
+}
+
+impl LodChain {
+    /// Simplifies the mesh into at most `levels` levels, stopping early when it can not be simplified further.
+    pub fn generate(mesh: &Mesh, levels: usize) -> Self {
+        let mut base = mesh.clone();
+        base.lods = Self::default();
+
+        let mut chain = Vec::<Mesh>::with_capacity(levels);
+        for _ in 0..levels {
+            let previous = chain.last().unwrap_or(&base);
+            let triangles = previous.indices.len();
+            if triangles < 8 {
+                break;
+            }
+            let level = previous.simplify(triangles / 4, f64::INFINITY);
+            // Collapses can get blocked by the topology, a level barely simpler than the last is not worth it.
+            if level.indices.len() * 10 > triangles * 9 {
+                break;
+            }
+            chain.push(level);
+        }
+        Self::new(mesh, chain)
+    }
+
+    /// Chain of already simplified `levels` of the mesh, ordered from the most detailed to the least.
+    pub fn new(mesh: &Mesh, levels: impl Into<Box<[Mesh]>>) -> Self {
+        let min = Vector::from(std::array::from_fn(|i| {
+            mesh.vertices
+                .iter()
+                .map(|v| v[i])
+                .fold(f64::INFINITY, f64::min)
+        }));
+        let max = Vector::from(std::array::from_fn(|i| {
+            mesh.vertices
+                .iter()
+                .map(|v| v[i])
+                .fold(f64::NEG_INFINITY, f64::max)
+        }));
+        Self {
+            bounds: (min, max),
+            levels: levels.into(),
+        }
+    }
+
+    pub fn levels(&self) -> &[Mesh] {
+        &self.levels
+    }
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// Size in pixels of the larger side of the screen rectangle the mesh bounds project to, through
+    /// the full model, camera and viewport `matrix`. Infinite when the bounds reach behind the camera.
+    pub fn projected_size(&self, matrix: &Matrix<4, 4>) -> f64 {
+        let (min, max) = self.bounds;
+        let (mut low, mut high) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+        for corner in 0..8 {
+            let [x, y, z] = [0, 1, 2].map(|i| {
+                if corner & (1 << i) == 0 {
+                    min[i]
+                } else {
+                    max[i]
+                }
+            });
+            let v = matrix.dot(&[x, y, z, 1.0].into());
+            if v[3] <= 0.0 {
+                return f64::INFINITY;
+            }
+            for i in 0..2 {
+                low[i] = low[i].min(v[i] / v[3]);
+                high[i] = high[i].max(v[i] / v[3]);
+            }
+        }
+        (high[0] - low[0]).max(high[1] - low[1])
+    }
+
+    /// Least detailed level with enough faces for a mesh `size` pixels large, `None` when only the full mesh has.
+    pub fn select(&self, size: f64) -> Option<&Mesh> {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.indices.len() as f64 * PIXELS_PER_TRIANGLE >= size * size)
+    }
+}
+
+impl Mesh {
+    /// Replaces the levels of detail with `levels` simplified versions of the mesh.
+    pub fn generate_lods(&mut self, levels: usize) {
+        self.lods = LodChain::generate(self, levels);
+    }
+
+    /// Level of the mesh to draw with the full model, camera and viewport `matrix`.
+    pub fn level_of_detail(&self, matrix: &Matrix<4, 4>) -> &Mesh {
+        if self.lods.is_empty() {
+            return self;
+        }
+        self.lods
+            .select(self.lods.projected_size(matrix))
+            .unwrap_or(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{primitives, transformations};
+
+    #[test]
+    fn test_level_of_detail() {
+        let mut sphere = primitives::ico_sphere(1.0, 4);
+        sphere.weld_vertices(1e-9);
+        sphere.generate_lods(3);
+
+        let levels = sphere.lods.levels();
+        assert_eq!(levels.len(), 3);
+        assert!(
+            levels
+                .windows(2)
+                .all(|pair| pair[1].indices.len() < pair[0].indices.len())
+        );
+
+        // Scaling the sphere to `size` pixels wide, with no perspective.
+        let at_size = |size: f64| transformations::scale((size / 2.0, size / 2.0, size / 2.0));
+        assert!(std::ptr::eq(
+            sphere.level_of_detail(&at_size(1000.0)),
+            &sphere
+        ));
+        assert!(std::ptr::eq(
+            sphere.level_of_detail(&at_size(2.0)),
+            &levels[2]
+        ));
+        let middle = sphere.level_of_detail(&at_size(
+            (levels[1].indices.len() as f64 * 4.0).sqrt() - 1.0,
+        ));
+        assert!(std::ptr::eq(middle, &levels[1]));
+    }
+}
@@ -0,0 +1,238 @@
+use serde::Deserialize;
+
+use super::{pixel::Pixel, sampler::Sampler, vector::Vector};
+
+/// Faces skipped when rasterizing, by which side of them faces the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cull {
+    #[default]
+    Back,
+    Front,
+    None,
+}
+
+/// Order the corners of a front face go around in, seen from the front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Winding {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+impl Winding {
+    pub fn reversed(self) -> Self {
+        match self {
+            Winding::CounterClockwise => Winding::Clockwise,
+            Winding::Clockwise => Winding::CounterClockwise,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FaceCulling {
+    pub cull: Cull,
+    pub front_face: Winding,
+}
+
+impl FaceCulling {
+    /// Culling of a double-sided surface, drawing every face.
+    pub const NONE: Self = Self {
+        cull: Cull::None,
+        front_face: Winding::CounterClockwise,
+    };
+
+    /// Whether a triangle with the screen-space determinant `det_abc` is skipped.
+    ///
+    /// The screen y axis points down, so counter-clockwise corners give a negative determinant.
+    pub fn culls(&self, det_abc: f64) -> bool {
+        let front = match self.front_face {
+            Winding::CounterClockwise => det_abc < 0.0,
+            Winding::Clockwise => det_abc > 0.0,
+        };
+        match self.cull {
+            Cull::Back => !front,
+            Cull::Front => front,
+            Cull::None => false,
+        }
+    }
+}
+
+/// What is drawn of a mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    /// Shaded faces.
+    #[default]
+    Shaded,
+    /// Every edge of the faces, through anything in front of them.
+    Wireframe,
+    /// Edges hidden by the faces in front of them, with the faces filled in the background colour.
+    HiddenLine,
+    /// Shaded faces with their visible edges drawn over them.
+    ShadedWireframe,
+}
+
+impl RenderMode {
+    /// The mode after this one, going back to the first after the last.
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Shaded => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::HiddenLine,
+            RenderMode::HiddenLine => RenderMode::ShadedWireframe,
+            RenderMode::ShadedWireframe => RenderMode::Shaded,
+        }
+    }
+}
+
+/// How a drawn pixel is combined with the one already on the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Blend {
+    /// Replaces it, writing depth.
+    #[default]
+    Opaque,
+    /// Mixes in by the alpha of the drawn pixel.
+    Alpha,
+    /// Adds the colour, scaled by alpha.
+    Additive,
+    /// Multiplies the colour, scaled by alpha.
+    Multiply,
+}
+
+impl Blend {
+    /// Colour of `destination` with `source` drawn over it.
+    pub fn apply(self, source: Pixel, destination: Pixel) -> Pixel {
+        let alpha = source.3 as f64 / 255.0;
+        let channel = |source: u8, destination: u8| {
+            let (source, destination) = (source as f64, destination as f64);
+            let value = match self {
+                Blend::Opaque => source,
+                Blend::Alpha => source * alpha + destination * (1.0 - alpha),
+                Blend::Additive => destination + source * alpha,
+                Blend::Multiply => destination * (1.0 - alpha + source / 255.0 * alpha),
+            };
+            value.round().clamp(0.0, 255.0) as u8
+        };
+        let coverage = match self {
+            Blend::Opaque => source.3,
+            // Whatever is drawn over, the pixel gets at least as covered as the drawn one.
+            _ => (source.3 as f64 + destination.3 as f64 * (1.0 - alpha)).round() as u8,
+        };
+        Pixel(
+            channel(source.0, destination.0),
+            channel(source.1, destination.1),
+            channel(source.2, destination.2),
+            coverage,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: Box<str>,
+
+    pub ambient: Vector<3>,
+    pub diffuse: Vector<3>,
+    pub specular: Vector<3>,
+    pub shininess: f64,
+    pub dissolve: f64,
+
+    pub diffuse_map: Option<Box<str>>,
+    pub bump_map: Option<Box<str>>,
+    /// How the textures are filtered and wrapped.
+    pub sampler: Sampler,
+
+    /// Faces skipped when drawing, unless the mesh sets its own.
+    pub culling: FaceCulling,
+    /// How the material is drawn over what is behind it, anything but opaque in the transparent pass.
+    pub blend: Blend,
+    /// Pixels with a lower alpha are discarded, for cutouts like foliage.
+    pub alpha_cutoff: Option<f64>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+
+            ambient: (1.0, 1.0, 1.0).into(),
+            diffuse: (1.0, 1.0, 1.0).into(),
+            specular: (0.0, 0.0, 0.0).into(),
+            shininess: 0.0,
+            dissolve: 1.0,
+
+            diffuse_map: None,
+            bump_map: None,
+            sampler: Sampler::default(),
+
+            culling: FaceCulling::default(),
+            blend: Blend::default(),
+            alpha_cutoff: None,
+        }
+    }
+
+    /// Material that only paints the given texture, used for textures referenced without a material library.
+    pub fn textured(name: &str, texture: &str) -> Self {
+        Self {
+            diffuse_map: Some(texture.into()),
+            ..Self::new(name)
+        }
+    }
+
+    /// Whether the material is drawn after the opaque ones, from back to front and without writing depth.
+    pub fn is_transparent(&self) -> bool {
+        self.blend != Blend::Opaque
+    }
+
+    /// All textures this material samples from.
+    pub fn textures(&self) -> impl Iterator<Item = &str> {
+        self.diffuse_map
+            .iter()
+            .chain(self.bump_map.iter())
+            .map(|name| name.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_culling() {
+        // Counter-clockwise on a screen with y pointing down has a negative determinant.
+        let (front, back) = (-2.0, 2.0);
+
+        let culling = FaceCulling::default();
+        assert!(!culling.culls(front) && culling.culls(back));
+
+        let culling = FaceCulling {
+            cull: Cull::Front,
+            front_face: Winding::Clockwise,
+        };
+        assert!(culling.culls(back) && !culling.culls(front));
+
+        assert!(!FaceCulling::NONE.culls(front) && !FaceCulling::NONE.culls(back));
+    }
+
+    #[test]
+    fn test_blend() {
+        let (source, destination) = (Pixel(200, 100, 0, 128), Pixel(100, 100, 100, 255));
+        assert_eq!(Blend::Opaque.apply(source, destination), source);
+        assert_eq!(
+            Blend::Alpha.apply(source, destination),
+            Pixel(150, 100, 50, 255)
+        );
+        assert_eq!(
+            Blend::Additive.apply(source, destination),
+            Pixel(200, 150, 100, 255)
+        );
+        assert_eq!(
+            Blend::Multiply.apply(source, destination),
+            Pixel(89, 69, 50, 255)
+        );
+        // Fully transparent pixels leave the screen as it was.
+        let clear = Pixel(255, 0, 0, 0);
+        assert_eq!(Blend::Alpha.apply(clear, destination), destination);
+    }
+}
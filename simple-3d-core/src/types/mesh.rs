@@ -1,42 +1,82 @@
+use std::ops::Range;
+
 use crate::transformations;
 
-use super::{matrix::Matrix, vector::Vector};
+use super::{
+    lod::LodChain,
+    material::{FaceCulling, RenderMode},
+    matrix::Matrix,
+    vector::Vector,
+};
 
-pub type Indice = ((usize, usize), (usize, usize), (usize, usize));
+/// Indexes of a single face corner: `(vertex, uv, normal)`.
+pub type Index = (usize, usize, usize);
+pub type Indice = (Index, Index, Index);
+/// Range of `Mesh::indices` drawn with the named material.
+pub type MaterialRange = (Range<usize>, Box<str>);
 
 #[derive(Debug, Clone)]
 pub struct Mesh {
+    pub name: Box<str>,
     pub vertices: Box<[Vector<3>]>,
     pub uvs: Box<[Vector<2>]>,
+    pub normals: Box<[Vector<3>]>,
     pub indices: Box<[Indice]>,
-    pub texture: Box<str>,
+    /// Per vertex RGBA colors in `0.0..=1.0`, indexed like `vertices`, empty if the mesh has none.
+    pub colors: Box<[Vector<4>]>,
+    pub materials: Box<[MaterialRange]>,
+    /// Material libraries (`.mtl` files) the mesh materials are defined in.
+    pub libraries: Box<[Box<str>]>,
+    /// Simplified versions drawn when the mesh is small on screen, made stale by editing the geometry.
+    pub lods: LodChain,
+    /// Faces skipped when drawing, `None` to use the culling of each material.
+    pub culling: Option<FaceCulling>,
+    /// What is drawn of the mesh, `None` to follow the mode of the app.
+    pub render_mode: Option<RenderMode>,
 
     scale: Vector<3>,
     rotation: Vector<3>,
     translation: Vector<3>,
     transformation: Matrix<4, 4>,
+    normal_transformation: Matrix<4, 4>,
 }
 
 impl Mesh {
     pub fn new(
         vertices: impl Into<Box<[Vector<3>]>>,
         uvs: impl Into<Box<[Vector<2>]>>,
+        normals: impl Into<Box<[Vector<3>]>>,
         indices: &[Indice],
-        texture: Box<str>,
+        materials: &[MaterialRange],
     ) -> Self {
         Self {
+            name: "default".into(),
             vertices: vertices.into(),
             uvs: uvs.into(),
+            normals: normals.into(),
             indices: indices.into(),
-            texture,
+            colors: Box::new([]),
+            materials: materials.into(),
+            libraries: Box::new([]),
+            lods: LodChain::default(),
+            culling: None,
+            render_mode: None,
 
             scale: (1.0, 1.0, 1.0).into(),
             rotation: (0.0, 0.0, 0.0).into(),
             translation: (0.0, 0.0, 0.0).into(),
             transformation: Matrix::identity(),
+            normal_transformation: Matrix::identity(),
         }
     }
 
+    /// Faces of the mesh grouped by the material they are drawn with.
+    pub fn material_groups(&self) -> impl Iterator<Item = (&[Indice], &str)> {
+        self.materials
+            .iter()
+            .map(|(range, material)| (&self.indices[range.clone()], material.as_ref()))
+    }
+
     pub fn scale(&mut self, scale: impl Into<Vector<3>>) {
         let [s_x, s_y, s_z] = *scale.into();
         let [x, y, z] = *self.scale;
@@ -55,10 +95,15 @@ impl Mesh {
     pub fn transformation_matrix(&self) -> &Matrix<4, 4> {
         &self.transformation
     }
+    pub fn normal_transformation_matrix(&self) -> &Matrix<4, 4> {
+        &self.normal_transformation
+    }
 
     #[inline]
     fn update_transformation(&mut self) {
         self.transformation =
             transformations::transformation(self.scale, self.rotation, self.translation);
+        self.normal_transformation =
+            transformations::normal_transformation(self.scale, self.rotation);
     }
 }
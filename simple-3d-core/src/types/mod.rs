@@ -1,9 +1,15 @@
 pub mod camera;
 pub mod keys;
+pub mod light;
+pub mod lod;
+pub mod material;
 pub mod matrix;
 pub mod mesh;
 pub mod pixel;
+pub mod sampler;
 pub mod screen;
+pub mod simplification;
 pub mod textures;
 pub mod triangle;
+pub mod validation;
 pub mod vector;
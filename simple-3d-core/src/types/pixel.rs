@@ -1,3 +1,5 @@
+use super::vector::Vector;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Pixel(pub u8, pub u8, pub u8, pub u8);
 
@@ -6,6 +8,49 @@ impl Pixel {
         let Pixel(r, g, b, _) = *self;
         (r as f32 + g as f32 + b as f32) / 3.0
     }
+
+    /// Multiplies the color channels by `color` and the alpha channel by `alpha`.
+    pub fn tinted(&self, color: Vector<3>, alpha: f64) -> Pixel {
+        let Pixel(r, g, b, a) = *self;
+        let channel =
+            |value: u8, factor: f64| (value as f64 * factor).round().clamp(0.0, 255.0) as u8;
+        Pixel(
+            channel(r, color[0]),
+            channel(g, color[1]),
+            channel(b, color[2]),
+            channel(a, alpha),
+        )
+    }
+
+    /// Adds `color` to the color channels, as a highlight.
+    pub fn highlighted(&self, color: Vector<3>) -> Pixel {
+        let Pixel(r, g, b, a) = *self;
+        let channel =
+            |value: u8, light: f64| (value as f64 + light * 255.0).round().clamp(0.0, 255.0) as u8;
+        Pixel(
+            channel(r, color[0]),
+            channel(g, color[1]),
+            channel(b, color[2]),
+            a,
+        )
+    }
+
+    /// Sum of the pixels scaled by their weights, which should add up to one.
+    pub fn average(weighted: &[(Pixel, f64)]) -> Pixel {
+        let channel = |get: fn(&Pixel) -> u8| {
+            let value = weighted
+                .iter()
+                .map(|(pixel, weight)| get(pixel) as f64 * weight)
+                .sum::<f64>();
+            value.round().clamp(0.0, 255.0) as u8
+        };
+        Pixel(
+            channel(|p| p.0),
+            channel(|p| p.1),
+            channel(|p| p.2),
+            channel(|p| p.3),
+        )
+    }
 }
 
 impl Default for Pixel {
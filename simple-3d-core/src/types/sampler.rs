@@ -0,0 +1,282 @@
+use super::{
+    pixel::Pixel,
+    textures::{Image, Texture},
+    vector::Vector,
+};
+
+/// How texels are combined for a point between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// The texel the point is in.
+    Nearest,
+    /// The four texels around the point, weighted by how close it is to their centres.
+    #[default]
+    Bilinear,
+}
+
+/// What texture coordinates outside `0.0..=1.0` sample.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Wrap {
+    Repeat,
+    /// Repeats the texture, flipping every other copy.
+    MirroredRepeat,
+    /// The texels on the edge of the texture.
+    #[default]
+    ClampToEdge,
+    /// A single colour, given as RGBA.
+    ClampToBorder([u8; 4]),
+}
+
+impl Wrap {
+    /// Texel index for `index` in a texture `size` texels long, `None` for the border.
+    fn texel(self, index: i64, size: u32) -> Option<u32> {
+        let size = size as i64;
+        let index = match self {
+            Wrap::Repeat => index.rem_euclid(size),
+            Wrap::MirroredRepeat => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+            Wrap::ClampToEdge => index.clamp(0, size - 1),
+            Wrap::ClampToBorder(_) => {
+                if !(0..size).contains(&index) {
+                    return None;
+                }
+                index
+            }
+        };
+        Some(index as u32)
+    }
+}
+
+/// How the mip levels of a texture are picked for the size it is drawn at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MipFilter {
+    /// Always the full size image.
+    None,
+    /// The level closest in size.
+    Nearest,
+    /// The two levels closest in size, blended. Trilinear filtering together with `Filter::Bilinear`.
+    #[default]
+    Linear,
+}
+
+/// How a material reads its textures, with `(u, v)` from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub mipmap: MipFilter,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+}
+
+impl Sampler {
+    pub fn new(filter: Filter, wrap: Wrap) -> Self {
+        Self {
+            filter,
+            mipmap: MipFilter::default(),
+            wrap_u: wrap,
+            wrap_v: wrap,
+        }
+    }
+
+    fn texel(&self, image: &Image, x: i64, y: i64) -> Pixel {
+        let border = |wrap: Wrap| match wrap {
+            Wrap::ClampToBorder([r, g, b, a]) => Pixel(r, g, b, a),
+            _ => Pixel::default(),
+        };
+        match (
+            self.wrap_u.texel(x, image.width()),
+            self.wrap_v.texel(y, image.height()),
+        ) {
+            (Some(x), Some(y)) => image.get(x, y),
+            (None, _) => border(self.wrap_u),
+            (_, None) => border(self.wrap_v),
+        }
+    }
+
+    /// Colour of a single mip level at `uv`.
+    fn filtered(&self, image: &Image, uv: Vector<2>) -> Pixel {
+        let (x, y) = (uv[0] * image.width() as f64, uv[1] * image.height() as f64);
+        match self.filter {
+            Filter::Nearest => self.texel(image, x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Texel centres are half way into them.
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (left, top) = (x.floor(), y.floor());
+                let (fx, fy) = (x - left, y - top);
+                let (left, top) = (left as i64, top as i64);
+
+                Pixel::average(&[
+                    (self.texel(image, left, top), (1.0 - fx) * (1.0 - fy)),
+                    (self.texel(image, left + 1, top), fx * (1.0 - fy)),
+                    (self.texel(image, left, top + 1), (1.0 - fx) * fy),
+                    (self.texel(image, left + 1, top + 1), fx * fy),
+                ])
+            }
+        }
+    }
+}
+
+impl Texture {
+    /// Colour of the full size texture at `uv`, where `(1.0, 1.0)` is the bottom right corner.
+    pub fn sample(&self, sampler: &Sampler, uv: Vector<2>) -> Pixel {
+        match self {
+            Texture::Image { image } => sampler.filtered(image, uv),
+            _ => self.get(0, 0),
+        }
+    }
+
+    /// Colour of the texture at `uv` for a pixel whose right and lower neighbours are `dx` and `dy`
+    /// away in texture coordinates. The mip level is picked by how many texels the pixel spans.
+    pub fn sample_gradient(
+        &self,
+        sampler: &Sampler,
+        uv: Vector<2>,
+        dx: Vector<2>,
+        dy: Vector<2>,
+    ) -> Pixel {
+        let Texture::Image { image } = self else {
+            return self.sample(sampler, uv);
+        };
+        let (width, height) = (image.width() as f64, image.height() as f64);
+        let texels = |d: Vector<2>| (d[0] * width).hypot(d[1] * height);
+        let lod = texels(dx).max(texels(dy)).log2();
+        // Magnified, or the derivatives are not usable.
+        if lod.is_nan() || lod <= 0.0 {
+            return sampler.filtered(image, uv);
+        }
+
+        let lod = lod.min((image.levels() - 1) as f64);
+        match sampler.mipmap {
+            MipFilter::None => sampler.filtered(image, uv),
+            MipFilter::Nearest => sampler.filtered(image.level(lod.round() as usize), uv),
+            MipFilter::Linear => {
+                let (level, t) = (lod.floor() as usize, lod.fract());
+                let finer = sampler.filtered(image.level(level), uv);
+                if t == 0.0 {
+                    return finer;
+                }
+                let coarser = sampler.filtered(image.level(level + 1), uv);
+                Pixel::average(&[(finer, 1.0 - t), (coarser, t)])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker() -> Texture {
+        // Black in the top left and bottom right, white in the others.
+        let (black, white) = (Pixel(0, 0, 0, 255), Pixel(255, 255, 255, 255));
+        Texture::Image {
+            image: Image::load(2, 2, &[black, white, white, black]),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let texture = checker();
+        let nearest = Sampler::new(Filter::Nearest, Wrap::ClampToEdge);
+        assert_eq!(
+            texture.sample(&nearest, [0.2, 0.2].into()),
+            Pixel(0, 0, 0, 255)
+        );
+        assert_eq!(
+            texture.sample(&nearest, [0.7, 0.2].into()),
+            Pixel(255, 255, 255, 255)
+        );
+
+        let bilinear = Sampler::new(Filter::Bilinear, Wrap::ClampToEdge);
+        // Centres of the texels are exact, the middle of the texture is an even mix.
+        assert_eq!(
+            texture.sample(&bilinear, [0.25, 0.25].into()),
+            Pixel(0, 0, 0, 255)
+        );
+        assert_eq!(
+            texture.sample(&bilinear, [0.5, 0.5].into()),
+            Pixel(128, 128, 128, 255)
+        );
+        assert_eq!(
+            texture.sample(&bilinear, [0.5, 0.25].into()),
+            Pixel(128, 128, 128, 255)
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let texture = checker();
+        let sample = |wrap: Wrap, u: f64| {
+            texture.sample(&Sampler::new(Filter::Nearest, wrap), [u, 0.25].into())
+        };
+        let (black, white) = (Pixel(0, 0, 0, 255), Pixel(255, 255, 255, 255));
+
+        assert_eq!(sample(Wrap::Repeat, 1.25), black);
+        assert_eq!(sample(Wrap::Repeat, -0.25), white);
+        assert_eq!(sample(Wrap::MirroredRepeat, 1.25), white);
+        assert_eq!(sample(Wrap::MirroredRepeat, -0.25), black);
+        assert_eq!(sample(Wrap::ClampToEdge, 3.0), white);
+        assert_eq!(sample(Wrap::ClampToEdge, -3.0), black);
+        assert_eq!(
+            sample(Wrap::ClampToBorder([255, 0, 0, 255]), 1.5),
+            Pixel(255, 0, 0, 255)
+        );
+        assert_eq!(sample(Wrap::ClampToBorder([255, 0, 0, 255]), 0.75), white);
+
+        // Without a wrap mode the edges are stretched, so textures do not bleed into each other.
+        let sampler = Sampler {
+            filter: Filter::Nearest,
+            ..Sampler::default()
+        };
+        assert_eq!(texture.sample(&sampler, [1.25, 0.25].into()), white);
+        assert_eq!(texture.sample(&sampler, [-0.25, 0.25].into()), black);
+    }
+
+    #[test]
+    fn test_mipmaps() {
+        // A 4x4 checker of single texels, which averages to grey from the first mip level on.
+        let (black, white) = (Pixel(0, 0, 0, 255), Pixel(255, 255, 255, 255));
+        let data = (0..16)
+            .map(|i| {
+                if (i % 4 + i / 4) % 2 == 0 {
+                    black
+                } else {
+                    white
+                }
+            })
+            .collect::<Vec<_>>();
+        let image = Image::load(4, 4, &data).with_mipmaps();
+        assert_eq!(image.levels(), 3);
+        assert_eq!((image.level(1).width(), image.level(1).height()), (2, 2));
+        assert_eq!(image.level(2).get(0, 0), Pixel(128, 128, 128, 255));
+        assert_eq!(image.level(9).width(), 1);
+
+        let texture = Texture::Image { image };
+        let sampler = Sampler::new(Filter::Nearest, Wrap::Repeat);
+        let sample = |sampler: &Sampler, step: f64| {
+            texture.sample_gradient(
+                sampler,
+                [0.1, 0.1].into(),
+                [step, 0.0].into(),
+                [0.0, step].into(),
+            )
+        };
+        // A texel per pixel reads the image itself, four texels per pixel the smallest level.
+        assert_eq!(sample(&sampler, 0.25), black);
+        assert_eq!(sample(&sampler, 1.0), Pixel(128, 128, 128, 255));
+        // Between levels 0 and 1, blended.
+        assert_eq!(sample(&sampler, 0.25 * 2f64.sqrt()), Pixel(64, 64, 64, 255));
+
+        let none = Sampler {
+            mipmap: MipFilter::None,
+            ..sampler
+        };
+        assert_eq!(sample(&none, 1.0), black);
+    }
+}
@@ -1,10 +1,97 @@
-use super::{matrix::Matrix, pixel::Pixel};
+use serde::Deserialize;
+
+use super::{material::Blend, matrix::Matrix, pixel::Pixel};
+
+/// Most samples a pixel of a multisampled screen can have.
+pub const MAX_SAMPLES: usize = 8;
+
+/// Sample positions within a pixel for 1, 2, 4 and 8 samples, in sixteenths of a pixel from its centre.
+const SAMPLE_PATTERNS: [&[(i8, i8)]; 4] = [
+    &[(0, 0)],
+    &[(4, 4), (-4, -4)],
+    &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+    &[
+        (1, -3),
+        (-1, 3),
+        (5, 1),
+        (-3, -5),
+        (-5, 5),
+        (-7, -1),
+        (3, 7),
+        (7, -7),
+    ],
+];
+
+/// How edges are smoothed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Antialiasing {
+    #[default]
+    None,
+    /// Supersampling: renders at this many times the width and height, at most `MAX_SAMPLES`, and
+    /// averages the result down.
+    Ssaa(u32),
+    /// Multisampling: this many samples of coverage and depth per pixel, shaded once.
+    Msaa(u32),
+}
+
+impl Antialiasing {
+    /// Screen the frame is drawn to, before `resolve`.
+    pub fn screen(self, width: u32, height: u32) -> Screen {
+        match self {
+            Antialiasing::None => Screen::new(width, height),
+            Antialiasing::Ssaa(_) => Screen::new(
+                width.saturating_mul(self.factor()),
+                height.saturating_mul(self.factor()),
+            ),
+            Antialiasing::Msaa(samples) => Screen::multisampled(width, height, samples),
+        }
+    }
+
+    /// The frame drawn to a screen from `screen`, at the requested size with a single sample per pixel.
+    pub fn resolve(self, screen: &Screen) -> Screen {
+        screen.resolve(self.factor())
+    }
+
+    /// How many times larger than the requested size the frame is drawn.
+    fn factor(self) -> u32 {
+        match self {
+            Antialiasing::Ssaa(factor) => factor.clamp(1, MAX_SAMPLES as u32),
+            _ => 1,
+        }
+    }
+}
+
+/// Pixel drawn by a triangle, covering some of the samples of a screen pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragment {
+    pub point: (u32, u32),
+    pub pixel: Pixel,
+    /// Bit `i` is set when sample `i` of the pixel is covered.
+    pub coverage: u32,
+    /// Depth at each covered sample.
+    pub depths: [f32; MAX_SAMPLES],
+}
+
+impl Fragment {
+    /// Fragment covering the whole pixel at the same depth.
+    pub fn covering(point: (u32, u32), z: f32, pixel: Pixel) -> Self {
+        Self {
+            point,
+            pixel,
+            coverage: u32::MAX,
+            depths: [z; MAX_SAMPLES],
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Screen {
     width: u32,
     height: u32,
+    samples: u32,
 
+    /// Colour and depth of every sample, the samples of a pixel next to each other.
     buffer: Box<[Pixel]>,
     depth: Box<[f32]>,
 
@@ -13,10 +100,20 @@ pub struct Screen {
 
 impl Screen {
     pub fn new(width: u32, height: u32) -> Self {
-        let size = (width * height) as usize;
+        Self::multisampled(width, height, 1)
+    }
+
+    /// Screen with `samples` samples per pixel, rounded down to 1, 2, 4 or 8.
+    pub fn multisampled(width: u32, height: u32, samples: u32) -> Self {
+        let samples = SAMPLE_PATTERNS[samples.clamp(1, MAX_SAMPLES as u32).ilog2() as usize].len();
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(samples))
+            .expect("screen too large to allocate");
         Self {
             width,
             height,
+            samples: samples as u32,
             buffer: vec![Pixel::default(); size].into(),
             depth: vec![f32::MAX; size].into(),
             transformation: Self::viewport_matrix(width, height),
@@ -42,6 +139,10 @@ impl Screen {
     pub fn transformation_matrix(&self) -> &Matrix<4, 4> {
         &self.transformation
     }
+    /// Positions of the samples within a pixel, in sixteenths of a pixel from its centre.
+    pub fn sample_offsets(&self) -> &'static [(i8, i8)] {
+        SAMPLE_PATTERNS[self.samples.ilog2() as usize]
+    }
 
     pub fn clear_buffer(&mut self) {
         self.buffer.fill(Pixel::default());
@@ -50,20 +151,132 @@ impl Screen {
         self.depth.fill(f32::MAX);
     }
 
-    pub fn put_pixel(&mut self, (x, y): (u32, u32), z: f32, pixel: Pixel) {
+    pub fn put_pixel(&mut self, point: (u32, u32), z: f32, pixel: Pixel) {
+        self.blend_pixel(point, z, pixel, Blend::Opaque);
+    }
+    /// Draws the pixel over the one on the screen if it passes the depth test.
+    /// Only opaque pixels write depth, so whatever is drawn later behind transparent ones still shows.
+    pub fn blend_pixel(&mut self, point: (u32, u32), z: f32, pixel: Pixel, blend: Blend) {
+        self.draw(&Fragment::covering(point, z, pixel), blend);
+    }
+    /// Like `blend_pixel`, testing and blending only the samples the fragment covers.
+    pub fn draw(&mut self, fragment: &Fragment, blend: Blend) {
+        let (x, y) = fragment.point;
         if x < self.width && y < self.height {
-            let index = (x + y * self.width) as usize;
-            if z.is_finite() && z < self.depth[index] {
-                self.buffer[index] = pixel;
-                self.depth[index] = z;
+            let start = (x as usize + y as usize * self.width as usize) * self.samples as usize;
+            let end = start + self.samples as usize;
+            draw_samples(
+                &mut self.buffer[start..end],
+                &mut self.depth[start..end],
+                fragment,
+                blend,
+            );
+        }
+    }
+
+    /// Single sample screen `factor` times smaller, each pixel averaging the samples of a
+    /// `factor` by `factor` block of pixels.
+    pub fn resolve(&self, factor: u32) -> Screen {
+        let (width, height) = (self.width / factor, self.height / factor);
+        let block = (factor * factor * self.samples) as usize;
+        let weight = 1.0 / block as f64;
+        let samples = self.samples as usize;
+        let mut resolved = Screen::new(width, height);
+        let mut weighted = Vec::with_capacity(block);
+        for y in 0..height {
+            for x in 0..width {
+                weighted.clear();
+                for row in y * factor..(y + 1) * factor {
+                    let start =
+                        (row as usize * self.width as usize + (x * factor) as usize) * samples;
+                    let end = start + factor as usize * samples;
+                    weighted.extend(self.buffer[start..end].iter().map(|&pixel| (pixel, weight)));
+                }
+                resolved.buffer[(x + y * width) as usize] = Pixel::average(&weighted);
             }
         }
+        resolved
+    }
+
+    /// Splits the screen into bands of `rows` full rows, the last one possibly shorter, that can be
+    /// drawn to independently.
+    pub fn bands_mut(&mut self, rows: u32) -> impl Iterator<Item = Band<'_>> {
+        let (width, samples) = (self.width, self.samples);
+        let size = (width as usize * rows as usize * samples as usize).max(1);
+        self.buffer
+            .chunks_mut(size)
+            .zip(self.depth.chunks_mut(size))
+            .enumerate()
+            .map(move |(i, (buffer, depth))| Band {
+                top: i as u32 * rows,
+                width,
+                samples,
+                buffer,
+                depth,
+            })
+    }
+}
+
+/// Horizontal band of the screen with its own part of the colour and depth buffers.
+pub struct Band<'a> {
+    top: u32,
+    width: u32,
+    samples: u32,
+    buffer: &'a mut [Pixel],
+    depth: &'a mut [f32],
+}
+
+impl Band<'_> {
+    /// First row of the band and the one after its last.
+    pub fn rows(&self) -> (u32, u32) {
+        let row = (self.width * self.samples).max(1);
+        (self.top, self.top + self.buffer.len() as u32 / row)
+    }
+
+    /// Like `Screen::draw`, with the point in screen coordinates.
+    pub fn draw(&mut self, fragment: &Fragment, blend: Blend) {
+        let (x, y) = fragment.point;
+        let (top, bottom) = self.rows();
+        if x < self.width && (top..bottom).contains(&y) {
+            let start =
+                (x as usize + (y - top) as usize * self.width as usize) * self.samples as usize;
+            let end = start + self.samples as usize;
+            draw_samples(
+                &mut self.buffer[start..end],
+                &mut self.depth[start..end],
+                fragment,
+                blend,
+            );
+        }
+    }
+}
+
+fn draw_samples(buffer: &mut [Pixel], depth: &mut [f32], fragment: &Fragment, blend: Blend) {
+    let samples = buffer.iter_mut().zip(depth.iter_mut()).enumerate();
+    for (i, (buffer, depth)) in samples {
+        if fragment.coverage & (1 << i) != 0 {
+            depth_test(buffer, depth, fragment.depths[i], fragment.pixel, blend);
+        }
+    }
+}
+
+#[inline]
+fn depth_test(buffer: &mut Pixel, depth: &mut f32, z: f32, pixel: Pixel, blend: Blend) {
+    if z.is_finite() && z < *depth {
+        *buffer = blend.apply(pixel, *buffer);
+        if blend == Blend::Opaque {
+            *depth = z;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::types::{pixel::Pixel, screen::Screen};
+    use crate::types::{
+        material::Blend,
+        pixel::Pixel,
+        screen::{Antialiasing, Fragment, MAX_SAMPLES, Screen},
+    };
 
     #[test]
     fn test_rasterize_point() {
@@ -85,4 +298,57 @@ mod tests {
         assert_eq!(screen.buffer[5 + 5 * 10], pixel);
         assert_eq!(screen.buffer[4 + 6 * 10], second_pixel);
     }
+
+    #[test]
+    fn test_blend_pixel() {
+        let mut screen = Screen::new(10, 10);
+        screen.put_pixel((5, 5), 0.5, Pixel(0, 0, 0, 255));
+
+        // Behind the opaque pixel nothing is drawn, in front it is blended without writing depth.
+        let glass = Pixel(255, 255, 255, 51);
+        screen.blend_pixel((5, 5), 0.6, glass, Blend::Alpha);
+        assert_eq!(screen.buffer[5 + 5 * 10], Pixel(0, 0, 0, 255));
+        screen.blend_pixel((5, 5), 0.3, glass, Blend::Alpha);
+        assert_eq!(screen.buffer[5 + 5 * 10], Pixel(51, 51, 51, 255));
+        assert_eq!(screen.depth[5 + 5 * 10], 0.5);
+
+        screen.put_pixel((5, 5), 0.4, Pixel(255, 0, 0, 255));
+        assert_eq!(screen.buffer[5 + 5 * 10], Pixel(255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut screen = Screen::new(4, 2);
+        screen.put_pixel((0, 0), 0.5, Pixel(0, 0, 0, 255));
+        screen.put_pixel((1, 1), 0.5, Pixel(0, 0, 0, 255));
+        let resolved = screen.resolve(2);
+        assert_eq!(resolved.size(), (2, 1));
+        assert_eq!(
+            resolved.buffer(),
+            [Pixel(128, 128, 128, 255), Pixel(255, 255, 255, 255)]
+        );
+
+        // Half the samples covered by a fragment, the other half keep the background.
+        let mut screen = Screen::multisampled(1, 1, 4);
+        assert_eq!(screen.sample_offsets().len(), 4);
+        let fragment = Fragment {
+            coverage: 0b0101,
+            ..Fragment::covering((0, 0), 0.5, Pixel(0, 0, 0, 255))
+        };
+        screen.draw(&fragment, Blend::Opaque);
+        assert_eq!(screen.resolve(1).buffer(), [Pixel(128, 128, 128, 255)]);
+    }
+
+    #[test]
+    fn test_antialiasing_screen() {
+        // Factors from a scene file are clamped instead of overflowing the size.
+        let max = MAX_SAMPLES as u32;
+        for (factor, size) in [(0, (3, 2)), (2, (6, 4)), (u32::MAX, (3 * max, 2 * max))] {
+            let antialiasing = Antialiasing::Ssaa(factor);
+            let screen = antialiasing.screen(3, 2);
+            assert_eq!(screen.size(), size);
+            assert_eq!(screen.buffer().len(), (size.0 * size.1) as usize);
+            assert_eq!(antialiasing.resolve(&screen).size(), (3, 2));
+        }
+    }
 }
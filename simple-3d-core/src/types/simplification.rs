@@ -0,0 +1,331 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashSet},
+};
+
+use super::{mesh::Mesh, vector::Vector};
+
+/// Sum of squared distances to a set of planes, as the upper triangle of a symmetric 4x4 matrix.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Quadric of the plane through `point` with the unit `normal`, scaled by `weight`.
+    fn plane(normal: Vector<3>, point: Vector<3>, weight: f64) -> Self {
+        let [a, b, c] = *normal;
+        let d = -normal.dot(point);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (q, o) in self.0.iter_mut().zip(other.0.iter()) {
+            *q += o;
+        }
+    }
+
+    fn error(&self, point: Vector<3>) -> f64 {
+        let [a, b, c, d, e, f, g, h, i, j] = self.0;
+        let [x, y, z] = *point;
+        a * x * x
+            + 2.0 * b * x * y
+            + 2.0 * c * x * z
+            + 2.0 * d * x
+            + e * y * y
+            + 2.0 * f * y * z
+            + 2.0 * g * y
+            + h * z * z
+            + 2.0 * i * z
+            + j
+    }
+
+    /// Point with the smallest error, `None` when the planes do not pin one down.
+    fn minimum(&self) -> Option<Vector<3>> {
+        let [a, b, c, d, e, f, g, h, i, _] = self.0;
+        let det3 = |m: [[f64; 3]; 3]| {
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        };
+        let matrix = [[a, b, c], [b, e, f], [c, f, h]];
+        let rhs = [-d, -g, -i];
+        let det = det3(matrix);
+        let scale = a.abs().max(e.abs()).max(h.abs());
+        if det.abs() <= 1e-9 * scale * scale * scale {
+            return None;
+        }
+        // Cramer's rule, replacing one column at a time with the right hand side.
+        Some(Vector::from(std::array::from_fn(|column| {
+            let mut m = matrix;
+            for row in 0..3 {
+                m[row][column] = rhs[row];
+            }
+            det3(m) / det
+        })))
+    }
+}
+
+/// Edge collapse waiting in the queue, outdated when either vertex changed since it was queued.
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    error: f64,
+    edge: (usize, usize),
+    versions: (u32, u32),
+    position: Vector<3>,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Collapse {}
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Ties are broken by the edge so the result does not depend on the queue order.
+        self.error
+            .total_cmp(&other.error)
+            .then(self.edge.cmp(&other.edge))
+    }
+}
+
+/// Weight of the planes keeping open borders in place, relative to the faces along them.
+const BORDER_WEIGHT: f64 = 1000.0;
+
+impl Mesh {
+    /// Simplifies the mesh by collapsing edges with the quadric error metric, until it has at most
+    /// `triangles` faces or the next collapse costs more than `max_error`.
+    ///
+    /// The error is the squared distance from the moved vertex to the planes of the original faces
+    /// around it, weighted by their area, so `0.0` only allows collapses that keep the shape exact.
+    ///
+    /// Collapses only move vertices, the uvs, normals and colors of the remaining corners are kept.
+    pub fn simplify(&self, triangles: usize, max_error: f64) -> Mesh {
+        let mut vertices = self.vertices.to_vec();
+        let mut faces = self
+            .indices
+            .iter()
+            .map(|&((a, _, _), (b, _, _), (c, _, _))| [a, b, c])
+            .collect::<Vec<_>>();
+        let mut removed = vec![false; faces.len()];
+        let mut vertex_faces = vec![Vec::new(); vertices.len()];
+        for (face, corners) in faces.iter().enumerate() {
+            for &vertex in corners {
+                vertex_faces[vertex].push(face);
+            }
+        }
+
+        let mut quadrics = vec![Quadric::default(); vertices.len()];
+        let mut edges = HashSet::new();
+        let mut borders = HashSet::new();
+        for &[a, b, c] in faces.iter() {
+            let (p_a, p_b, p_c) = (vertices[a], vertices[b], vertices[c]);
+            let normal = (p_b - p_a).cross(p_c - p_a);
+            if normal.magnitude() == 0.0 {
+                continue;
+            }
+            // Weighting by area keeps small faces from outvoting large ones.
+            let quadric = Quadric::plane(normal.normalize(), p_a, normal.magnitude() / 2.0);
+            for vertex in [a, b, c] {
+                quadrics[vertex].add(&quadric);
+            }
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                let edge = (from.min(to), from.max(to));
+                edges.insert(edge);
+                // Edges seen once are on a border, the second time they are inside the surface.
+                if !borders.remove(&edge) {
+                    borders.insert(edge);
+                }
+            }
+        }
+        let mut borders = borders.into_iter().collect::<Vec<_>>();
+        borders.sort_unstable();
+        for &(a, b) in borders.iter() {
+            let [p_a, p_b] = [a, b].map(|vertex| vertices[vertex]);
+            let Some(face) = vertex_faces[a]
+                .iter()
+                .find(|&&face| faces[face].contains(&b))
+            else {
+                continue;
+            };
+            let [f_a, f_b, f_c] = faces[*face].map(|vertex| vertices[vertex]);
+            let face_normal = (f_b - f_a).cross(f_c - f_a);
+            // Plane through the border, perpendicular to the face.
+            let normal = (p_b - p_a).cross(face_normal);
+            if normal.magnitude() == 0.0 {
+                continue;
+            }
+            let length = (p_b - p_a).magnitude();
+            let quadric = Quadric::plane(normal.normalize(), p_a, BORDER_WEIGHT * length * length);
+            quadrics[a].add(&quadric);
+            quadrics[b].add(&quadric);
+        }
+
+        let mut versions = vec![0u32; vertices.len()];
+        let collapse = |(a, b): (usize, usize),
+                        quadrics: &[Quadric],
+                        vertices: &[Vector<3>],
+                        versions: &[u32]| {
+            let mut quadric = quadrics[a];
+            quadric.add(&quadrics[b]);
+            let candidates = [vertices[a], vertices[b], (vertices[a] + vertices[b]) * 0.5];
+            let position = quadric
+                .minimum()
+                .into_iter()
+                .chain(candidates)
+                .min_by(|&p, &q| quadric.error(p).total_cmp(&quadric.error(q)))
+                .unwrap();
+            Reverse(Collapse {
+                error: quadric.error(position).max(0.0),
+                edge: (a, b),
+                versions: (versions[a], versions[b]),
+                position,
+            })
+        };
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        edges.sort_unstable();
+        let mut queue = edges
+            .into_iter()
+            .map(|edge| collapse(edge, &quadrics, &vertices, &versions))
+            .collect::<BinaryHeap<_>>();
+
+        let mut remaining = faces.len();
+        while remaining > triangles
+            && let Some(Reverse(next)) = queue.pop()
+        {
+            let (a, b) = next.edge;
+            if next.versions != (versions[a], versions[b]) {
+                continue;
+            }
+            if next.error > max_error {
+                break;
+            }
+
+            // Faces around either vertex, other than the ones collapsing, must not turn over.
+            let folds = [a, b].iter().any(|&vertex| {
+                vertex_faces[vertex].iter().any(|&face| {
+                    let corners = faces[face];
+                    if removed[face] || (corners.contains(&a) && corners.contains(&b)) {
+                        return false;
+                    }
+                    let [p_a, p_b, p_c] = corners.map(|corner| vertices[corner]);
+                    let moved = corners.map(|corner| {
+                        if corner == a || corner == b {
+                            next.position
+                        } else {
+                            vertices[corner]
+                        }
+                    });
+                    let before = (p_b - p_a).cross(p_c - p_a);
+                    let after = (moved[1] - moved[0]).cross(moved[2] - moved[0]);
+                    before.dot(after) <= 0.0
+                })
+            });
+            if folds {
+                // Either vertex changing may make the collapse possible again.
+                continue;
+            }
+
+            vertices[a] = next.position;
+            let quadric = quadrics[b];
+            quadrics[a].add(&quadric);
+            versions[a] += 1;
+            versions[b] += 1;
+            for face in std::mem::take(&mut vertex_faces[b]) {
+                if removed[face] {
+                    continue;
+                }
+                if faces[face].contains(&a) {
+                    removed[face] = true;
+                    remaining -= 1;
+                } else {
+                    for corner in faces[face].iter_mut().filter(|corner| **corner == b) {
+                        *corner = a;
+                    }
+                    vertex_faces[a].push(face);
+                }
+            }
+            vertex_faces[a].retain(|&face| !removed[face]);
+            vertex_faces[a].sort_unstable();
+            vertex_faces[a].dedup();
+
+            let mut neighbours = vertex_faces[a]
+                .iter()
+                .flat_map(|&face| faces[face])
+                .filter(|&vertex| vertex != a)
+                .collect::<Vec<_>>();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            for neighbour in neighbours {
+                let edge = (a.min(neighbour), a.max(neighbour));
+                queue.push(collapse(edge, &quadrics, &vertices, &versions));
+            }
+        }
+
+        let mut mesh = self.clone();
+        mesh.vertices = vertices.into();
+        for (face, corners) in mesh.indices.iter_mut().zip(faces.iter()) {
+            face.0.0 = corners[0];
+            face.1.0 = corners[1];
+            face.2.0 = corners[2];
+        }
+        mesh.retain_faces(|face, _| !removed[face]);
+        mesh.remove_unreferenced_vertices();
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitives;
+
+    #[test]
+    fn test_simplify_flat() {
+        // A flat grid can go down to two triangles without any error.
+        let plane = primitives::plane(2.0, 2.0, 8, 8);
+        let simplified = plane.simplify(0, 1e-6);
+        assert_eq!(simplified.indices.len(), 2);
+        assert_eq!(simplified.vertices.len(), 4);
+        for vertex in simplified.vertices.iter() {
+            assert!((vertex[0].abs() - 1.0).abs() < 1e-9 && (vertex[2].abs() - 1.0).abs() < 1e-9);
+        }
+        assert!(simplified.validate().is_valid());
+    }
+
+    #[test]
+    fn test_simplify_sphere() {
+        let mut sphere = primitives::ico_sphere(1.0, 3);
+        sphere.weld_vertices(1e-9);
+        let simplified = sphere.simplify(sphere.indices.len() / 4, f64::INFINITY);
+        assert!(simplified.indices.len() <= sphere.indices.len() / 4);
+        assert!(simplified.indices.len() > sphere.indices.len() / 5);
+        for vertex in simplified.vertices.iter() {
+            assert!((vertex.magnitude() - 1.0).abs() < 0.1);
+        }
+        assert!(simplified.validate().is_valid());
+
+        // Nothing is collapsed when even the cheapest collapse is over the error.
+        assert_eq!(
+            sphere.simplify(0, 1e-12).indices.len(),
+            sphere.indices.len()
+        );
+    }
+}
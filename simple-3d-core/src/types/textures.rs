@@ -1,15 +1,30 @@
 use std::collections::HashMap;
 
-use super::pixel::Pixel;
+use super::{material::Material, pixel::Pixel};
 
-pub struct Textures(HashMap<Box<str>, Texture>);
+pub struct Textures {
+    textures: HashMap<Box<str>, Texture>,
+    materials: HashMap<Box<str>, Material>,
+}
 
 impl Textures {
     pub fn get(&self, name: &str) -> &Texture {
-        self.0.get(name).unwrap_or(&Texture::None)
+        self.textures.get(name).unwrap_or(&Texture::None)
+    }
+    pub fn material(&self, name: &str) -> &Material {
+        self.materials
+            .get(name)
+            .unwrap_or_else(|| &self.materials["none"])
+    }
+    /// Texture painted by the material, `Texture::None` if the material has no diffuse map.
+    pub fn diffuse(&self, material: &Material) -> &Texture {
+        material
+            .diffuse_map
+            .as_deref()
+            .map_or(&Texture::None, |name| self.get(name))
     }
 
-    pub fn new(images: HashMap<Box<str>, Image>) -> Self {
+    pub fn new(images: HashMap<Box<str>, Image>, materials: HashMap<Box<str>, Material>) -> Self {
         let mut textures = images
             .into_iter()
             .map(|(name, image)| (name, Texture::Image { image }))
@@ -17,11 +32,25 @@ impl Textures {
         textures.insert("none".into(), Texture::None);
         textures.insert("solid_red".into(), Texture::Solid(255, 0, 0, 255));
 
-        Self(textures)
+        // Meshes may reference a texture directly instead of a material defined in a library.
+        let mut all_materials = textures
+            .keys()
+            .map(|name| (name.clone(), Material::textured(name, name)))
+            .collect::<HashMap<Box<str>, Material>>();
+        all_materials.insert("none".into(), Material::new("none"));
+        all_materials.extend(materials);
+
+        Self {
+            textures,
+            materials: all_materials,
+        }
     }
 
     pub fn add(&mut self, name: &str, texture: Texture) {
-        self.0.insert(name.into(), texture);
+        self.textures.insert(name.into(), texture);
+    }
+    pub fn add_material(&mut self, material: Material) {
+        self.materials.insert(material.name.clone(), material);
     }
 }
 
@@ -62,6 +91,8 @@ pub struct Image {
     data: Box<[Pixel]>,
     width: u32,
     height: u32,
+    /// Smaller copies of the image, each half the size of the one before down to a single pixel.
+    mipmaps: Box<[Image]>,
 }
 
 impl Image {
@@ -70,9 +101,64 @@ impl Image {
             data: data.into(),
             width,
             height,
+            mipmaps: Box::new([]),
+        }
+    }
+
+    /// The image with a full mip chain, each level averaging 2x2 texels of the one above it.
+    pub fn with_mipmaps(self) -> Self {
+        let mut mipmaps = Vec::new();
+        let mut level = &self;
+        while level.width > 1 || level.height > 1 {
+            mipmaps.push(level.downsampled());
+            level = mipmaps.last().unwrap();
+        }
+        Self {
+            mipmaps: mipmaps.into(),
+            ..self
         }
     }
 
+    fn downsampled(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (left, top) = (2 * x, 2 * y);
+                let (right, bottom) = (
+                    (left + 1).min(self.width - 1),
+                    (top + 1).min(self.height - 1),
+                );
+                let texels = [
+                    self.get(left, top),
+                    self.get(right, top),
+                    self.get(left, bottom),
+                    self.get(right, bottom),
+                ];
+                Pixel::average(&texels.map(|texel| (texel, 0.25)))
+            })
+            .collect::<Box<[Pixel]>>();
+        Self::load(width, height, &data)
+    }
+
+    /// Number of mip levels, `1` when the image has no mip chain.
+    pub fn levels(&self) -> usize {
+        self.mipmaps.len() + 1
+    }
+    /// Mip level `level`, `0` being the image itself. Levels past the smallest give the smallest.
+    pub fn level(&self, level: usize) -> &Image {
+        match level.min(self.mipmaps.len()) {
+            0 => self,
+            level => &self.mipmaps[level - 1],
+        }
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
     #[inline]
     pub fn get(&self, x: u32, y: u32) -> Pixel {
         let y = y * self.width;
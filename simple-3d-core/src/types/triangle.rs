@@ -1,34 +1,96 @@
-use super::{mesh::Indice, vector::Vector};
+use std::rc::Rc;
 
+use crate::clip::{ClipVertex, Clipping, clip_triangle};
+
+use super::{matrix::Matrix, mesh::Indice, vector::Vector};
+
+/// Triangles of the faces in screen space, clipped to the view volume before the perspective divide.
 pub struct TriangleIterator<'a, I: Iterator<Item = &'a Indice>> {
-    vertices: Box<[Vector<3>]>,
-    uvs: Box<[Vector<2>]>,
+    /// Vertices in homogeneous clip space, with their positions in world space.
+    vertices: Rc<[(Vector<4>, Vector<3>)]>,
+    uvs: &'a [Vector<2>],
+    normals: Rc<[Vector<3>]>,
+    colors: &'a [Vector<4>],
     indices: I,
+    viewport: Matrix<4, 4>,
+    clipping: Clipping,
+    /// Rest of a face clipped into more than one triangle, in reverse order.
+    pending: Vec<Triangle>,
 }
 impl<'a, I: Iterator<Item = &'a Indice>> Iterator for TriangleIterator<'a, I> {
     type Item = Triangle;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (a, b, c) = self.indices.next()?;
+        loop {
+            if let Some(triangle) = self.pending.pop() {
+                return Some(triangle);
+            }
 
-        Some(Triangle(
-            Vertex::new(self.vertices[a.0], self.uvs[a.1]),
-            Vertex::new(self.vertices[b.0], self.uvs[b.1]),
-            Vertex::new(self.vertices[c.0], self.uvs[c.1]),
-        ))
+            let (a, b, c) = self.indices.next()?;
+            let vertex = |(position, uv, normal): (usize, usize, usize)| {
+                let color = self
+                    .colors
+                    .get(position)
+                    .copied()
+                    .unwrap_or_else(|| (1.0, 1.0, 1.0, 1.0).into());
+                let (clip, world) = self.vertices[position];
+                ClipVertex {
+                    position: clip,
+                    world,
+                    uv: self.uvs[uv],
+                    normal: self.normals[normal],
+                    color,
+                }
+            };
+            let polygon = clip_triangle([vertex(*a), vertex(*b), vertex(*c)], self.clipping);
+            let polygon = polygon
+                .iter()
+                .map(|vertex| self.project(vertex))
+                .collect::<Vec<_>>();
+            // Clipping leaves a convex polygon, split into a fan around its first corner.
+            for i in (1..polygon.len().saturating_sub(1)).rev() {
+                self.pending
+                    .push(Triangle(polygon[0], polygon[i], polygon[i + 1]));
+            }
+        }
     }
 }
 
 impl<'a, I: Iterator<Item = &'a Indice>> TriangleIterator<'a, I> {
     pub fn new(
-        vertices: &[Vector<3>],
-        uvs: &[Vector<2>],
+        vertices: impl Into<Rc<[(Vector<4>, Vector<3>)]>>,
+        uvs: &'a [Vector<2>],
+        normals: impl Into<Rc<[Vector<3>]>>,
+        colors: &'a [Vector<4>],
         indices: impl IntoIterator<Item = &'a Indice, IntoIter = I>,
+        viewport: Matrix<4, 4>,
+        clipping: Clipping,
     ) -> Self {
         Self {
             vertices: vertices.into(),
-            uvs: uvs.into(),
+            uvs,
+            normals: normals.into(),
+            colors,
             indices: indices.into_iter(),
+            viewport,
+            clipping,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Divides by w and maps to the screen, keeping the normalized depth and `1 / w`.
+    fn project(&self, vertex: &ClipVertex) -> Vertex {
+        let [x, y, z, w] = *vertex.position;
+        let screen = self.viewport.dot(&[x / w, y / w, z / w, 1.0].into());
+        Vertex {
+            world: vertex.world,
+            inv_w: 1.0 / w,
+            ..Vertex::new(
+                (screen[0], screen[1], z / w),
+                vertex.uv,
+                vertex.normal,
+                vertex.color,
+            )
         }
     }
 }
@@ -36,24 +98,50 @@ impl<'a, I: Iterator<Item = &'a Indice>> TriangleIterator<'a, I> {
 #[derive(Debug, Clone)]
 pub struct Triangle(pub Vertex, pub Vertex, pub Vertex);
 
+/// Transforms the vertices to homogeneous clip space, leaving the perspective divide until after clipping.
 pub fn transform(
-    transformation: &super::matrix::Matrix<4, 4>,
+    transformation: &Matrix<4, 4>,
     vertexes: impl Iterator<Item = Vector<3>>,
+) -> impl Iterator<Item = Vector<4>> {
+    vertexes.map(|v| transformation.dot(&v.homogenous()))
+}
+
+pub fn transform_normals(
+    transformation: &Matrix<4, 4>,
+    normals: impl Iterator<Item = Vector<3>>,
 ) -> impl Iterator<Item = Vector<3>> {
-    vertexes.map(|v| v.transformed(transformation))
+    normals.map(|n| n.transformed_normal(transformation))
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Vertex {
+    /// Screen x and y with the normalized depth, which is linear across the face on screen.
     pub position: Vector<3>,
+    /// One over w in clip space, which the other attributes are weighted by to follow the perspective.
+    pub inv_w: f64,
+    /// Position in world space, for lighting.
+    pub world: Vector<3>,
     pub uv: Vector<2>,
+    pub normal: Vector<3>,
+    pub color: Vector<4>,
 }
 
 impl Vertex {
-    pub fn new(position: impl Into<Vector<3>>, texture: impl Into<Vector<2>>) -> Self {
+    pub fn new(
+        position: impl Into<Vector<3>>,
+        texture: impl Into<Vector<2>>,
+        normal: impl Into<Vector<3>>,
+        color: impl Into<Vector<4>>,
+    ) -> Self {
+        let position = position.into();
+        // Vertices made straight in screen space have no other position.
         Self {
-            position: position.into(),
+            position,
+            world: position,
+            inv_w: 1.0,
             uv: texture.into(),
+            normal: normal.into(),
+            color: color.into(),
         }
     }
 }
@@ -0,0 +1,741 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    ops::Range,
+};
+
+use super::{
+    mesh::{Indice, Mesh},
+    vector::Vector,
+};
+
+/// Problem found by `Mesh::validate`, faces and vertices given by their index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Face corner referencing a vertex, uv or normal past the end of its array, which can not be rendered.
+    IndexOutOfRange {
+        face: usize,
+        element: &'static str,
+        index: usize,
+        len: usize,
+    },
+    /// Vertex, uv or normal with a NaN or infinite component.
+    NonFinite {
+        element: &'static str,
+        index: usize,
+    },
+    /// Face with no area, repeating a vertex or with all of them on a line.
+    DegenerateFace {
+        face: usize,
+    },
+    /// Face over the same vertices as an earlier one.
+    DuplicateFace {
+        face: usize,
+        original: usize,
+    },
+    UnreferencedVertex {
+        vertex: usize,
+    },
+    /// Neighbouring faces going along their shared edge in the same direction, so one of them is flipped.
+    InconsistentWinding {
+        face: usize,
+        neighbour: usize,
+    },
+    /// Material range reaching past the faces of the mesh.
+    InvalidMaterialRange {
+        range: Range<usize>,
+    },
+    /// Face outside every material range, which is not drawn.
+    UncoveredFace {
+        face: usize,
+    },
+    /// Vertex colors not matching the vertices one to one.
+    ColorCount {
+        colors: usize,
+        vertices: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::IndexOutOfRange {
+                face,
+                element,
+                index,
+                len,
+            } => write!(f, "face {face} references {element} {index}, {len} defined"),
+            Issue::NonFinite { element, index } => {
+                write!(f, "{element} {index} is not finite")
+            }
+            Issue::DegenerateFace { face } => write!(f, "face {face} has no area"),
+            Issue::DuplicateFace { face, original } => {
+                write!(f, "face {face} duplicates face {original}")
+            }
+            Issue::UnreferencedVertex { vertex } => {
+                write!(f, "vertex {vertex} is not used by any face")
+            }
+            Issue::InconsistentWinding { face, neighbour } => write!(
+                f,
+                "face {face} is wound opposite to its neighbour {neighbour}"
+            ),
+            Issue::InvalidMaterialRange { range } => {
+                write!(f, "material range {range:?} is past the faces")
+            }
+            Issue::UncoveredFace { face } => write!(f, "face {face} has no material"),
+            Issue::ColorCount { colors, vertices } => {
+                write!(f, "{colors} colors for {vertices} vertices")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+    /// Whether the mesh can be drawn at all, ignoring issues that only make it look wrong.
+    pub fn is_renderable(&self) -> bool {
+        !self.issues.iter().any(|issue| {
+            matches!(
+                issue,
+                Issue::IndexOutOfRange { .. } | Issue::InvalidMaterialRange { .. }
+            )
+        })
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            writeln!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Steps `Mesh::repair` runs, in the order they are listed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RepairOptions {
+    /// Merges vertices closer than the tolerance, `None` to keep them all.
+    pub weld_tolerance: Option<f64>,
+    pub remove_degenerate_faces: bool,
+    pub remove_duplicate_faces: bool,
+    pub remove_unreferenced_vertices: bool,
+    pub fix_winding: bool,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: Some(1e-9),
+            remove_degenerate_faces: true,
+            remove_duplicate_faces: true,
+            remove_unreferenced_vertices: true,
+            fix_winding: true,
+        }
+    }
+}
+
+/// Number of elements each step of `Mesh::repair` changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    pub invalid_faces: usize,
+    pub welded_vertices: usize,
+    pub degenerate_faces: usize,
+    pub duplicate_faces: usize,
+    pub unreferenced_vertices: usize,
+    pub flipped_faces: usize,
+}
+
+/// Whether the triangle has no area, relative to the length of its longest edge.
+pub(crate) fn is_degenerate(a: Vector<3>, b: Vector<3>, c: Vector<3>) -> bool {
+    let longest = (b - a).magnitude().max((c - a).magnitude());
+    let area = (b - a).cross(c - a).magnitude();
+    area.is_nan() || area <= 1e-12 * longest * longest
+}
+
+#[inline]
+fn is_finite<const S: usize>(vector: &Vector<S>) -> bool {
+    vector.iter().all(|value| value.is_finite())
+}
+
+impl Mesh {
+    pub fn validate(&self) -> ValidationReport {
+        let mut issues = Vec::new();
+
+        for (element, items) in [
+            (
+                "vertex",
+                self.vertices.iter().map(is_finite).collect::<Vec<_>>(),
+            ),
+            ("uv", self.uvs.iter().map(is_finite).collect()),
+            ("normal", self.normals.iter().map(is_finite).collect()),
+        ] {
+            issues.extend(
+                items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, finite)| !**finite)
+                    .map(|(index, _)| Issue::NonFinite { element, index }),
+            );
+        }
+        if !self.colors.is_empty() && self.colors.len() != self.vertices.len() {
+            issues.push(Issue::ColorCount {
+                colors: self.colors.len(),
+                vertices: self.vertices.len(),
+            });
+        }
+        issues.extend(
+            self.materials
+                .iter()
+                .filter(|(range, _)| range.end > self.indices.len())
+                .map(|(range, _)| Issue::InvalidMaterialRange {
+                    range: range.clone(),
+                }),
+        );
+        let covered = self.covered_faces();
+        issues.extend(
+            covered
+                .iter()
+                .enumerate()
+                .filter(|(_, covered)| !**covered)
+                .map(|(face, _)| Issue::UncoveredFace { face }),
+        );
+
+        let mut faces = HashMap::<[usize; 3], usize>::new();
+        let mut edges = HashMap::<(usize, usize), usize>::new();
+        let mut referenced = vec![false; self.vertices.len()];
+        for (face, &(a, b, c)) in self.indices.iter().enumerate() {
+            let out_of_range = [a, b, c]
+                .iter()
+                .flat_map(|&(vertex, uv, normal)| {
+                    [
+                        ("vertex", vertex, self.vertices.len()),
+                        ("uv", uv, self.uvs.len()),
+                        ("normal", normal, self.normals.len()),
+                    ]
+                })
+                .filter(|&(_, index, len)| index >= len)
+                .map(|(element, index, len)| Issue::IndexOutOfRange {
+                    face,
+                    element,
+                    index,
+                    len,
+                })
+                .collect::<Vec<_>>();
+            if !out_of_range.is_empty() {
+                issues.extend(out_of_range);
+                continue;
+            }
+
+            let corners = [a.0, b.0, c.0];
+            for &vertex in corners.iter() {
+                referenced[vertex] = true;
+            }
+            let [p_a, p_b, p_c] = corners.map(|vertex| self.vertices[vertex]);
+            if corners[0] == corners[1]
+                || corners[1] == corners[2]
+                || corners[2] == corners[0]
+                || is_degenerate(p_a, p_b, p_c)
+            {
+                issues.push(Issue::DegenerateFace { face });
+                continue;
+            }
+
+            let mut key = corners;
+            key.sort_unstable();
+            if let Some(&original) = faces.get(&key) {
+                issues.push(Issue::DuplicateFace { face, original });
+                continue;
+            }
+            faces.insert(key, face);
+
+            for edge in [(a.0, b.0), (b.0, c.0), (c.0, a.0)] {
+                // Consistently wound neighbours go along their shared edge in opposite directions.
+                match edges.get(&edge) {
+                    Some(&neighbour) => {
+                        issues.push(Issue::InconsistentWinding { face, neighbour });
+                    }
+                    None => {
+                        edges.insert(edge, face);
+                    }
+                }
+            }
+        }
+        issues.extend(
+            referenced
+                .iter()
+                .enumerate()
+                .filter(|(_, referenced)| !**referenced)
+                .map(|(vertex, _)| Issue::UnreferencedVertex { vertex }),
+        );
+
+        ValidationReport { issues }
+    }
+
+    /// Runs the repair steps enabled in `options`, dropping faces that can not be rendered first.
+    pub fn repair(&mut self, options: &RepairOptions) -> RepairReport {
+        let mut report = RepairReport {
+            invalid_faces: self.remove_invalid_faces(),
+            ..Default::default()
+        };
+        if let Some(tolerance) = options.weld_tolerance {
+            report.welded_vertices = self.weld_vertices(tolerance);
+        }
+        if options.remove_degenerate_faces {
+            report.degenerate_faces = self.remove_degenerate_faces();
+        }
+        if options.remove_duplicate_faces {
+            report.duplicate_faces = self.remove_duplicate_faces();
+        }
+        if options.remove_unreferenced_vertices {
+            report.unreferenced_vertices = self.remove_unreferenced_vertices();
+        }
+        if options.fix_winding {
+            report.flipped_faces = self.fix_winding();
+        }
+        report
+    }
+
+    /// Removes faces referencing elements that do not exist or vertices that are not finite,
+    /// returning how many were removed.
+    pub fn remove_invalid_faces(&mut self) -> usize {
+        let (vertices, uvs, normals) = (self.vertices.clone(), self.uvs.len(), self.normals.len());
+        self.retain_faces(|_, &(a, b, c)| {
+            [a, b, c].iter().all(|&(vertex, uv, normal)| {
+                vertices.get(vertex).is_some_and(is_finite) && uv < uvs && normal < normals
+            })
+        })
+    }
+
+    /// Merges vertices closer than `tolerance` into the first of them, returning how many were merged.
+    ///
+    /// A tolerance of zero only merges vertices at exactly the same position.
+    pub fn weld_vertices(&mut self, tolerance: f64) -> usize {
+        // Vertices are bucketed into cells the size of the tolerance, so only neighbouring cells are searched.
+        // Cells far from the origin saturate, which only makes them hold more vertices.
+        let cell = |vertex: &Vector<3>| vertex.map(|value: f64| (value / tolerance).floor() as i64);
+        // Adding zero turns -0.0 into 0.0, so both weld together.
+        let exact = |vertex: &Vector<3>| vertex.map(|value: f64| (value + 0.0).to_bits() as i64);
+
+        let mut cells = HashMap::<[i64; 3], Vec<usize>>::new();
+        let mut remap = Vec::with_capacity(self.vertices.len());
+        let mut kept = Vec::<usize>::new();
+        for vertex in self.vertices.iter() {
+            let (key, found) = if tolerance > 0.0 {
+                let [x, y, z] = cell(vertex);
+                let found = (-1..=1)
+                    .flat_map(|dx: i64| {
+                        (-1..=1).flat_map(move |dy: i64| {
+                            (-1..=1).map(move |dz: i64| {
+                                [
+                                    x.saturating_add(dx),
+                                    y.saturating_add(dy),
+                                    z.saturating_add(dz),
+                                ]
+                            })
+                        })
+                    })
+                    .filter_map(|key| cells.get(&key))
+                    .flatten()
+                    .copied()
+                    .find(|&index: &usize| {
+                        (self.vertices[kept[index]] - *vertex).magnitude() <= tolerance
+                    });
+                ([x, y, z], found)
+            } else {
+                let key = exact(vertex);
+                (key, cells.get(&key).map(|indices| indices[0]))
+            };
+            match found {
+                Some(index) => remap.push(index),
+                None => {
+                    kept.push(remap.len());
+                    remap.push(kept.len() - 1);
+                    cells.entry(key).or_default().push(kept.len() - 1);
+                }
+            }
+        }
+
+        let welded = self.vertices.len() - kept.len();
+        if welded > 0 {
+            self.keep_vertices(&kept, &remap);
+        }
+        welded
+    }
+
+    /// Removes faces with no area, returning how many were removed.
+    pub fn remove_degenerate_faces(&mut self) -> usize {
+        let vertices = self.vertices.clone();
+        self.retain_faces(|_, &((a, _, _), (b, _, _), (c, _, _))| {
+            a != b && b != c && c != a && !is_degenerate(vertices[a], vertices[b], vertices[c])
+        })
+    }
+
+    /// Removes faces over the same vertices as an earlier face, returning how many were removed.
+    pub fn remove_duplicate_faces(&mut self) -> usize {
+        let mut faces = HashSet::new();
+        self.retain_faces(|_, &((a, _, _), (b, _, _), (c, _, _))| {
+            let mut key = [a, b, c];
+            key.sort_unstable();
+            faces.insert(key)
+        })
+    }
+
+    /// Removes vertices, uvs and normals no face uses, returning how many vertices were removed.
+    pub fn remove_unreferenced_vertices(&mut self) -> usize {
+        fn compact(used: &[bool]) -> (Vec<usize>, Vec<usize>) {
+            let mut kept = Vec::new();
+            let remap = used
+                .iter()
+                .enumerate()
+                .map(|(index, &used)| {
+                    if used {
+                        kept.push(index);
+                    }
+                    kept.len().saturating_sub(1)
+                })
+                .collect();
+            (kept, remap)
+        }
+
+        let mut used = (
+            vec![false; self.vertices.len()],
+            vec![false; self.uvs.len()],
+            vec![false; self.normals.len()],
+        );
+        for &(a, b, c) in self.indices.iter() {
+            for (vertex, uv, normal) in [a, b, c] {
+                used.0[vertex] = true;
+                used.1[uv] = true;
+                used.2[normal] = true;
+            }
+        }
+
+        let (vertices, vertex_remap) = compact(&used.0);
+        let (uvs, uv_remap) = compact(&used.1);
+        let (normals, normal_remap) = compact(&used.2);
+        let removed = self.vertices.len() - vertices.len();
+
+        self.keep_vertices(&vertices, &vertex_remap);
+        self.uvs = uvs.iter().map(|&index| self.uvs[index]).collect();
+        self.normals = normals.iter().map(|&index| self.normals[index]).collect();
+        for face in self.indices.iter_mut() {
+            for corner in [&mut face.0, &mut face.1, &mut face.2] {
+                corner.1 = uv_remap[corner.1];
+                corner.2 = normal_remap[corner.2];
+            }
+        }
+        removed
+    }
+
+    /// Flips faces so neighbours sharing an edge are wound the same way, returning how many were flipped.
+    ///
+    /// Each connected part keeps the orientation most of its faces agree with their normals on.
+    pub fn fix_winding(&mut self) -> usize {
+        let mut edges = HashMap::<(usize, usize), Vec<usize>>::new();
+        for (face, &((a, _, _), (b, _, _), (c, _, _))) in self.indices.iter().enumerate() {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                edges
+                    .entry((from.min(to), from.max(to)))
+                    .or_default()
+                    .push(face);
+            }
+        }
+        let directed = |face: &Indice, from: usize, to: usize| {
+            let &((a, _, _), (b, _, _), (c, _, _)) = face;
+            [(a, b), (b, c), (c, a)].contains(&(from, to))
+        };
+
+        let mut flip = vec![None::<bool>; self.indices.len()];
+        let mut flipped = 0;
+        for start in 0..self.indices.len() {
+            if flip[start].is_some() {
+                continue;
+            }
+            flip[start] = Some(false);
+            let mut part = vec![start];
+            let mut queue = VecDeque::from([start]);
+            while let Some(face) = queue.pop_front() {
+                let ((a, _, _), (b, _, _), (c, _, _)) = self.indices[face];
+                let (a, b, c) = if flip[face] == Some(true) {
+                    (a, c, b)
+                } else {
+                    (a, b, c)
+                };
+                for (from, to) in [(a, b), (b, c), (c, a)] {
+                    // Only manifold edges tell how the neighbour should be wound.
+                    let [one, other] = edges[&(from.min(to), from.max(to))][..] else {
+                        continue;
+                    };
+                    let neighbour = if one == face { other } else { one };
+                    if flip[neighbour].is_none() {
+                        // The neighbour has to go along the shared edge the other way.
+                        flip[neighbour] = Some(directed(&self.indices[neighbour], from, to));
+                        part.push(neighbour);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            // Faces agreeing with their normals vote against flipping the part.
+            let votes = part
+                .iter()
+                .map(|&face| {
+                    let ((a, _, na), (b, _, nb), (c, _, nc)) = self.indices[face];
+                    let normal = self.normals[na] + self.normals[nb] + self.normals[nc];
+                    let (p_a, p_b, p_c) = (self.vertices[a], self.vertices[b], self.vertices[c]);
+                    let agrees = (p_b - p_a).cross(p_c - p_a).dot(normal) >= 0.0;
+                    if agrees == (flip[face] == Some(true)) {
+                        1
+                    } else {
+                        -1
+                    }
+                })
+                .sum::<i64>();
+            let invert = votes > 0;
+            for &face in part.iter() {
+                if (flip[face] == Some(true)) != invert {
+                    let (a, b, c) = self.indices[face];
+                    self.indices[face] = (a, c, b);
+                    flipped += 1;
+                }
+            }
+        }
+        flipped
+    }
+
+    /// Whether each face is in a material range.
+    fn covered_faces(&self) -> Vec<bool> {
+        let mut covered = vec![false; self.indices.len()];
+        for (range, _) in self.materials.iter() {
+            let range = range.start.min(covered.len())..range.end.min(covered.len());
+            covered[range].fill(true);
+        }
+        covered
+    }
+
+    /// Keeps the faces `keep` returns true for, given their index, shrinking the material ranges to match.
+    ///
+    /// Kept faces outside every material range are moved to a `"none"` range at the end.
+    pub(super) fn retain_faces(&mut self, mut keep: impl FnMut(usize, &Indice) -> bool) -> usize {
+        let mut indices = Vec::with_capacity(self.indices.len());
+        let mut materials = Vec::with_capacity(self.materials.len());
+        for (range, material) in self.materials.iter() {
+            let start = indices.len();
+            let range = range.start.min(self.indices.len())..range.end.min(self.indices.len());
+            indices.extend(
+                self.indices[range.clone()]
+                    .iter()
+                    .zip(range)
+                    .filter(|(face, index)| keep(*index, face))
+                    .map(|(face, _)| *face),
+            );
+            if indices.len() > start {
+                materials.push((start..indices.len(), material.clone()));
+            }
+        }
+        let start = indices.len();
+        for (index, covered) in self.covered_faces().into_iter().enumerate() {
+            if !covered && keep(index, &self.indices[index]) {
+                indices.push(self.indices[index]);
+            }
+        }
+        if indices.len() > start {
+            materials.push((start..indices.len(), "none".into()));
+        }
+        let removed = self.indices.len() - indices.len();
+        self.indices = indices.into();
+        self.materials = materials.into();
+        removed
+    }
+
+    /// Keeps the vertices (and their colors) at the `kept` indices, `remap` mapping old indices to new ones.
+    fn keep_vertices(&mut self, kept: &[usize], remap: &[usize]) {
+        self.vertices = kept.iter().map(|&index| self.vertices[index]).collect();
+        if self.colors.len() >= remap.len() {
+            self.colors = kept.iter().map(|&index| self.colors[index]).collect();
+        }
+        for face in self.indices.iter_mut() {
+            for corner in [&mut face.0, &mut face.1, &mut face.2] {
+                corner.0 = remap[corner.0];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    fn quad() -> Mesh {
+        // Two triangles of a unit square facing +z, the second one wound the wrong way.
+        Mesh::new(
+            [
+                (0.0, 0.0, 0.0).into(),
+                (1.0, 0.0, 0.0).into(),
+                (1.0, 1.0, 0.0).into(),
+                (0.0, 1.0, 0.0).into(),
+            ],
+            [(0.0, 0.0).into()],
+            [(0.0, 0.0, 1.0).into()],
+            &[
+                ((0, 0, 0), (1, 0, 0), (2, 0, 0)),
+                ((0, 0, 0), (3, 0, 0), (2, 0, 0)),
+            ],
+            &[(0..2, "none".into())],
+        )
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(primitives::plane(1.0, 1.0, 2, 2).validate().is_valid());
+
+        let mut mesh = quad();
+        mesh.vertices[1] = (f64::NAN, 0.0, 0.0).into();
+        mesh.indices = [
+            mesh.indices[0],
+            mesh.indices[1],
+            ((0, 0, 0), (4, 0, 0), (2, 0, 0)),
+            ((0, 0, 0), (0, 0, 0), (2, 0, 0)),
+            ((2, 0, 0), (0, 0, 0), (3, 0, 0)),
+        ]
+        .into();
+        mesh.materials = [(0..6, "none".into())].into();
+
+        let report = mesh.validate();
+        assert!(!report.is_renderable());
+        assert_eq!(
+            report.issues,
+            [
+                Issue::NonFinite {
+                    element: "vertex",
+                    index: 1
+                },
+                Issue::InvalidMaterialRange { range: 0..6 },
+                Issue::DegenerateFace { face: 0 },
+                Issue::IndexOutOfRange {
+                    face: 2,
+                    element: "vertex",
+                    index: 4,
+                    len: 4
+                },
+                Issue::DegenerateFace { face: 3 },
+                Issue::DuplicateFace {
+                    face: 4,
+                    original: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix_winding() {
+        let mut mesh = quad();
+        assert_eq!(
+            mesh.validate().issues,
+            [Issue::InconsistentWinding {
+                face: 1,
+                neighbour: 0
+            }]
+        );
+        assert_eq!(mesh.fix_winding(), 1);
+        assert_eq!(mesh.indices[1], ((0, 0, 0), (2, 0, 0), (3, 0, 0)));
+        assert!(mesh.validate().is_valid());
+
+        // Faces disagreeing with their normals are flipped as a whole part.
+        let mut mesh = primitives::plane(1.0, 1.0, 2, 2);
+        for face in mesh.indices.iter_mut() {
+            *face = (face.0, face.2, face.1);
+        }
+        assert_eq!(mesh.fix_winding(), 8);
+        assert_eq!(mesh.indices, primitives::plane(1.0, 1.0, 2, 2).indices);
+    }
+
+    #[test]
+    fn test_repair() {
+        // The seam and pole vertices of a uv sphere are duplicated, welding them closes it up.
+        let mut sphere = primitives::uv_sphere(1.0, 8, 4);
+        let report = sphere.repair(&RepairOptions::default());
+        assert_eq!(report.welded_vertices, 2 * 8 + 3);
+        assert_eq!(report.unreferenced_vertices, 0);
+        assert_eq!(report.flipped_faces, 0);
+        assert_eq!(sphere.vertices.len(), 8 * 3 + 2);
+        assert!(sphere.validate().is_valid());
+
+        let mut mesh = quad();
+        mesh.vertices = [
+            mesh.vertices.to_vec(),
+            vec![(1.0, 1e-12, 0.0).into(), (5.0, 5.0, 5.0).into()],
+        ]
+        .concat()
+        .into();
+        mesh.colors = vec![(1.0, 1.0, 1.0, 1.0).into(); 6].into();
+        mesh.indices = [
+            mesh.indices[0],
+            mesh.indices[1],
+            ((0, 0, 0), (4, 0, 0), (2, 0, 0)),
+            ((0, 0, 0), (2, 0, 0), (2, 0, 0)),
+            ((0, 0, 0), (9, 0, 0), (3, 0, 0)),
+        ]
+        .into();
+        mesh.materials = [(0..2, "a".into()), (2..5, "b".into())].into();
+
+        let report = mesh.repair(&RepairOptions::default());
+        assert_eq!(
+            report,
+            RepairReport {
+                invalid_faces: 1,
+                welded_vertices: 1,
+                degenerate_faces: 1,
+                duplicate_faces: 1,
+                unreferenced_vertices: 1,
+                flipped_faces: 1,
+            }
+        );
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.colors.len(), 4);
+        assert_eq!(&*mesh.materials, &[(0..2, "a".into())]);
+        assert!(mesh.validate().is_valid());
+
+        // Faces without a material are kept, with the default one.
+        let mut mesh = quad();
+        mesh.materials = [(0..1, "a".into())].into();
+        assert!(
+            mesh.validate()
+                .issues
+                .contains(&Issue::UncoveredFace { face: 1 })
+        );
+        mesh.repair(&RepairOptions::default());
+        assert_eq!(mesh.indices.len(), 2);
+        assert_eq!(
+            &*mesh.materials,
+            &[(0..1, "a".into()), (1..2, "none".into())]
+        );
+        assert!(mesh.validate().is_valid());
+    }
+
+    #[test]
+    fn test_weld_vertices() {
+        let mut mesh = quad();
+        mesh.vertices = [
+            (1e300, 0.0, 0.0).into(),
+            (1e300, 0.0, 0.0).into(),
+            (0.0, -1e300, 0.0).into(),
+            (0.0, -0.0, 1e-300).into(),
+            (0.0, 0.0, 1e-300).into(),
+        ]
+        .into();
+        // Only the same positions weld without a tolerance.
+        assert_eq!(mesh.clone().weld_vertices(0.0), 2);
+        // Saturated cells still only weld vertices within the tolerance.
+        assert_eq!(mesh.weld_vertices(1.0), 2);
+    }
+}
@@ -14,6 +14,12 @@ impl<const S: usize> Vector<S> {
     pub fn dot(&self, other: Self) -> f64 {
         zip(self.0.iter(), other.0.iter()).map(|(a, b)| a * b).sum()
     }
+    /// Component-wise product.
+    pub fn scaled(&self, other: Self) -> Self {
+        zip(self.0.iter(), other.0.iter())
+            .map(|(a, b)| a * b)
+            .collect()
+    }
 }
 
 impl Vector<3> {
@@ -26,19 +32,24 @@ impl Vector<3> {
     }
 
     pub fn homogenous(&self) -> Vector<4> {
-        [
-            self.0[0],
-            self.0[1],
-            if self.0[2] == 0.0 { 0.0001 } else { self.0[2] },
-            1.0,
-        ]
-        .into()
+        [self.0[0], self.0[1], self.0[2], 1.0].into()
     }
 
     pub fn transformed(&self, transformation: &super::matrix::Matrix<4, 4>) -> Self {
         let v = transformation.dot(&self.homogenous());
         (v[0] / v[3], v[1] / v[3], v[2] / v[3]).into()
     }
+
+    /// Transforms the vector as a direction (ignoring translation) and normalizes the result.
+    pub fn transformed_normal(&self, transformation: &super::matrix::Matrix<4, 4>) -> Self {
+        let v = transformation.dot(&[self.0[0], self.0[1], self.0[2], 0.0].into());
+        let v: Self = (v[0], v[1], v[2]).into();
+        if v.magnitude() > 0.0 {
+            v.normalize()
+        } else {
+            v
+        }
+    }
 }
 
 impl<const S: usize> std::ops::Index<usize> for Vector<S> {
@@ -110,6 +121,13 @@ impl<const S: usize> std::ops::Sub for Vector<S> {
     }
 }
 
+impl<const S: usize> std::ops::Mul<f64> for Vector<S> {
+    type Output = Self;
+    fn mul(self, factor: f64) -> Self::Output {
+        self.0.iter().map(|a| a * factor).collect()
+    }
+}
+
 impl<const S: usize> From<[f64; S]> for Vector<S> {
     fn from(arr: [f64; S]) -> Self {
         Self(arr)
@@ -130,6 +148,11 @@ impl From<(f64, f64, f64)> for Vector<3> {
         Self([x, y, z])
     }
 }
+impl From<(f64, f64, f64, f64)> for Vector<4> {
+    fn from((x, y, z, w): (f64, f64, f64, f64)) -> Self {
+        Self([x, y, z, w])
+    }
+}
 impl<const S: usize> FromIterator<f64> for Vector<S> {
     fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
         iter.into_iter().collect::<Vec<_>>().into()
@@ -69,12 +69,8 @@ impl simple_3d_core::Interface for WasmInterface {
 
         for (key, on_hold) in keys {
             register_event_listener("keydown", move |event: KeyboardEvent| {
-                let pressed = match event.key().as_str() {
-                    "ArrowUp" => Key::ArrowUp,
-                    "ArrowDown" => Key::ArrowDown,
-                    "ArrowLeft" => Key::ArrowLeft,
-                    "ArrowRight" => Key::ArrowRight,
-                    _ => return,
+                let Some(pressed) = Key::from_name(&event.key()) else {
+                    return;
                 };
                 if key == pressed {
                     on_hold();
@@ -1,9 +1,4 @@
-use std::collections::HashMap;
-
-use simple_3d_core::{
-    init, load_image, load_obj,
-    types::{mesh::Mesh, textures::Image},
-};
+use simple_3d_core::{LoadError, load_scene_assets};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
@@ -39,59 +34,54 @@ pub(crate) fn context() -> CanvasRenderingContext2d {
 
 #[wasm_bindgen(start)]
 pub async fn start() -> Result<(), JsValue> {
-    let (objects, images) = load_objects(&["cube.obj"]).await;
-
-    let app = init::<interface::WasmInterface>(objects, images);
+    let assets = load_scene_assets("scene.toml", |path| {
+        let url = format!("./assets/{}", path);
+        async move {
+            fetch_binary_data(&url).await.map_err(|error| {
+                let reason = error.as_string().unwrap_or_else(|| format!("{:?}", error));
+                LoadError::Fetch(reason.into())
+            })
+        }
+    })
+    .await
+    .map_err(|error| {
+        let message = error.to_string();
+        web_sys::console::error_1(&message.as_str().into());
+        JsValue::from_str(&message)
+    })?;
+
+    let app = assets.init::<interface::WasmInterface>();
     app.wait();
 
     Ok(())
 }
 
-async fn load_objects(paths: &[&str]) -> (Box<[Mesh]>, HashMap<Box<str>, Image>) {
-    let mut objects = Vec::new();
-    let mut images = HashMap::new();
-    for path in paths {
-        let object = load_obj(&load_binary_asset(path).await);
-        let texture = object.texture.clone();
-        let image = load_image(&load_binary_asset(&texture).await);
-        images.insert(texture, image);
-        objects.push(object);
-    }
-    (objects.into(), images)
-}
-
-async fn load_binary_asset(name: &str) -> Box<[u8]> {
-    fetch_binary_data(format!("./assets/{}", name).as_str()).await
-}
-
-async fn fetch_binary_data(url: &str) -> Box<[u8]> {
+async fn fetch_binary_data(url: &str) -> Result<Box<[u8]>, JsValue> {
     let resp_value = window().fetch_with_str(url);
     let resp = JsFuture::from(resp_value)
-        .await
-        .expect("Failed to fetch data")
-        .dyn_into::<web_sys::Response>()
-        .expect("Failed to convert to Response");
+        .await?
+        .dyn_into::<web_sys::Response>()?;
 
     if !resp.ok() {
-        panic!("Failed to fetch data");
+        return Err(JsValue::from_str(&format!(
+            "{} {}",
+            resp.status(),
+            resp.status_text()
+        )));
     }
 
-    let blob_promise = resp.blob().expect("Failed to get blob");
+    let blob_promise = resp.blob()?;
     let blob = JsFuture::from(blob_promise)
-        .await
-        .expect("Failed to convert to Blob")
-        .dyn_into::<web_sys::Blob>()
-        .expect("Failed to convert to Blob");
+        .await?
+        .dyn_into::<web_sys::Blob>()?;
 
     let array_buffer_promise = blob.array_buffer();
-    let array_buffer = JsFuture::from(array_buffer_promise)
-        .await
-        .expect("Failed to get ArrayBuffer");
+    let array_buffer = JsFuture::from(array_buffer_promise).await?;
 
     let uint8_array = web_sys::js_sys::Uint8Array::new(&array_buffer);
 
     let mut vec = vec![0; uint8_array.length() as usize];
     uint8_array.copy_to(&mut vec[..]);
 
-    vec.into()
+    Ok(vec.into())
 }